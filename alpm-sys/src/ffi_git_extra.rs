@@ -0,0 +1,79 @@
+// Declarations for libalpm git functions that are missing from ffi_git.rs.
+//
+// ffi_git.rs is generated by bindgen and must not be edited by hand. These were written
+// from alpm.h instead, as the headers the bindings were generated from predate them.
+// Once ffi_git.rs is regenerated with the bindgen script against a libalpm that has them,
+// delete this file.
+
+use crate::ffi_git::{alpm_db_t, alpm_handle_t, alpm_list_t};
+
+extern "C" {
+    #[doc = " Get the list of cache servers assigned to this db."]
+    #[doc = " @param db pointer to the database to get the servers from"]
+    #[doc = " @return a char* list of servers"]
+    pub fn alpm_db_get_cache_servers(db: *const alpm_db_t) -> *mut alpm_list_t;
+}
+extern "C" {
+    #[doc = " Sets the list of cache servers for the database to use."]
+    #[doc = " @param db the database to set the servers. The list will be duped and"]
+    #[doc = " the original will still need to be freed by the caller."]
+    #[doc = " @param servers a char* list of servers."]
+    pub fn alpm_db_set_cache_servers(
+        db: *mut alpm_db_t,
+        servers: *mut alpm_list_t,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    #[doc = " Add a download cache server to a database."]
+    #[doc = " @param db database pointer"]
+    #[doc = " @param url url of the server"]
+    #[doc = " @return 0 on success, -1 on error (pm_errno is set accordingly)"]
+    pub fn alpm_db_add_cache_server(
+        db: *mut alpm_db_t,
+        url: *const ::std::os::raw::c_char,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    #[doc = " Remove a download cache server from a database."]
+    #[doc = " @param db database pointer"]
+    #[doc = " @param url url of the server"]
+    #[doc = " @return 0 on success, 1 on server not present,"]
+    #[doc = " -1 on error (pm_errno is set accordingly)"]
+    pub fn alpm_db_remove_cache_server(
+        db: *mut alpm_db_t,
+        url: *const ::std::os::raw::c_char,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    #[doc = " Get the user to switch to when downloading."]
+    #[doc = " @param handle the context handle"]
+    #[doc = " @return the sandbox user, or NULL if none is set"]
+    pub fn alpm_option_get_sandboxuser(handle: *mut alpm_handle_t)
+        -> *const ::std::os::raw::c_char;
+}
+extern "C" {
+    #[doc = " Sets the user to switch to when downloading."]
+    #[doc = " @param handle the context handle"]
+    #[doc = " @param sandboxuser the user to set, or NULL to not switch user"]
+    #[doc = " @return 0 on success, -1 on error (pm_errno is set accordingly)"]
+    pub fn alpm_option_set_sandboxuser(
+        handle: *mut alpm_handle_t,
+        sandboxuser: *const ::std::os::raw::c_char,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    #[doc = " Get whether the sandbox is disabled."]
+    #[doc = " @param handle the context handle"]
+    #[doc = " @return 0 for enabled, 1 for disabled"]
+    pub fn alpm_option_get_disable_sandbox(handle: *mut alpm_handle_t) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    #[doc = " Enables/disables the sandbox."]
+    #[doc = " @param handle the context handle"]
+    #[doc = " @param disable_sandbox 0 for enabled, 1 for disabled"]
+    #[doc = " @return 0 on success, -1 on error (pm_errno is set accordingly)"]
+    pub fn alpm_option_set_disable_sandbox(
+        handle: *mut alpm_handle_t,
+        disable_sandbox: ::std::os::raw::c_ushort,
+    ) -> ::std::os::raw::c_int;
+}
@@ -7,6 +7,8 @@ mod ffi;
 
 #[cfg(all(feature = "git", not(feature = "generate")))]
 mod ffi_git;
+#[cfg(all(feature = "git", not(feature = "generate")))]
+mod ffi_git_extra;
 
 #[cfg(feature = "generate")]
 mod ffi_generated;
@@ -16,6 +18,8 @@ pub use crate::ffi::*;
 
 #[cfg(all(feature = "git", not(feature = "generate")))]
 pub use crate::ffi_git::*;
+#[cfg(all(feature = "git", not(feature = "generate")))]
+pub use crate::ffi_git_extra::*;
 
 #[cfg(feature = "generate")]
 pub use crate::ffi_generated::*;
@@ -102,9 +102,6 @@ fn register_db(alpm: &mut alpm::Alpm, repo: &pacmanconf::Repository) -> alpm::Re
         parse_sig_level(&repo.sig_level)
     };
 
-    let db = alpm.register_syncdb_mut(&*repo.name, sig)?;
-    db.set_servers(repo.servers.iter())?;
-
     let mut usage = Usage::NONE;
 
     for v in &repo.usage {
@@ -121,6 +118,9 @@ fn register_db(alpm: &mut alpm::Alpm, repo: &pacmanconf::Repository) -> alpm::Re
         }
     }
 
-    db.set_usage(usage)?;
+    alpm.register_syncdb_with(&*repo.name, sig, |db| {
+        db.set_servers(repo.servers.iter())?;
+        db.set_usage(usage)
+    })?;
     Ok(())
 }
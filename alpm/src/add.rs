@@ -27,6 +27,10 @@ unsafe impl<'a> IntoPkgAdd for LoadedPackage<'a> {
 }
 
 impl Alpm {
+    /// Adds a package to the transaction to be installed.
+    ///
+    /// On success the transaction takes ownership of the package. On failure the package
+    /// is handed back in the [`AddError`].
     pub fn trans_add_pkg<P: IntoPkgAdd>(&self, pkg: P) -> Result<(), AddError<P>> {
         let ret = unsafe { alpm_add_pkg(self.handle, pkg.as_alpm_pkg_t()) };
         let ok = self.check_ret(ret);
@@ -59,3 +63,43 @@ impl<P> From<AddError<P>> for Error {
         err.err
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{Alpm, Error, LoadedPackage, SigLevel, TransFlag};
+
+    fn load(handle: &Alpm) -> LoadedPackage<'_> {
+        handle
+            .pkg_load(
+                "tests/pacman-5.1.3-1-x86_64.pkg.tar.xz",
+                true,
+                SigLevel::NONE,
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn test_add_loaded_pkg() {
+        let mut handle = Alpm::new("/", "tests/db").unwrap();
+        let pkg = load(&handle);
+        let err = handle.trans_add_pkg(pkg).unwrap_err();
+        assert_eq!(err.err, Error::TransNull);
+        assert_eq!(err.pkg.name(), "pacman");
+        drop(err);
+
+        handle
+            .trans_init(TransFlag::DB_ONLY | TransFlag::NO_LOCK)
+            .unwrap();
+        handle.trans_add_pkg(load(&handle)).unwrap();
+
+        let err = handle.trans_add_pkg(load(&handle)).unwrap_err();
+        assert_eq!(err.err, Error::TransDupTarget);
+        assert_eq!(err.pkg.version().as_str(), "5.1.3-1");
+        drop(err);
+
+        let added = handle.trans_add();
+        assert_eq!(added.len(), 1);
+        assert_eq!(added.first().unwrap().name(), "pacman");
+        handle.trans_release().unwrap();
+    }
+}
@@ -1,7 +1,7 @@
 use crate::utils::*;
 use crate::{Callbacks, Error, Result};
 
-use std::ffi::{c_void, CString};
+use std::ffi::c_void;
 use std::os::raw::c_int;
 
 use alpm_sys::*;
@@ -11,6 +11,15 @@ extern "C" {
     pub(crate) fn free(ptr: *mut c_void);
 }
 
+/// A libalpm handle.
+///
+/// `Alpm` is not `Send` as its callbacks may hold data that must stay on one thread. To move
+/// a handle to another thread, or share one behind a `Mutex`, use [`SendAlpm`].
+///
+/// ```compile_fail
+/// fn assert_send<T: Send>(_: T) {}
+/// assert_send(alpm::Alpm::new("/", "tests/db").unwrap());
+/// ```
 #[allow(dead_code)]
 pub struct Alpm {
     pub(crate) handle: *mut alpm_handle_t,
@@ -19,12 +28,27 @@ pub struct Alpm {
 
 impl std::fmt::Debug for Alpm {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("Alpm").finish()
+        let syncdbs = self.syncdbs();
+        let syncdbs = syncdbs.iter().map(|db| (db.name(), db.servers().len()));
+
+        let mut s = f.debug_struct("Alpm");
+        s.field("root", &self.root())
+            .field("dbpath", &self.dbpath())
+            .field("lockfile", &self.lockfile())
+            .field("cachedirs", &DebugTruncated(self.cachedirs().iter()))
+            .field("syncdbs", &DebugTruncated(syncdbs))
+            .field("default_siglevel", &self.default_siglevel())
+            .field("local_file_siglevel", &self.local_file_siglevel())
+            .field("remote_file_siglevel", &self.remote_file_siglevel())
+            .field("ignorepkgs", &DebugTruncated(self.ignorepkgs().iter()))
+            .field("parallel_downloads", &self.parallel_downloads());
+        #[cfg(feature = "git")]
+        s.field("sandbox_user", &self.sandbox_user())
+            .field("disable_sandbox", &self.disable_sandbox());
+        s.finish()
     }
 }
 
-unsafe impl Send for Alpm {}
-
 impl Drop for Alpm {
     fn drop(&mut self) {
         unsafe { alpm_release(self.handle) };
@@ -35,8 +59,8 @@ impl Alpm {
     #[doc(alias = "initialize")]
     pub fn new<S: Into<Vec<u8>>>(root: S, db_path: S) -> Result<Alpm> {
         let mut err = alpm_errno_t::ALPM_ERR_OK;
-        let root = CString::new(root).unwrap();
-        let db_path = CString::new(db_path).unwrap();
+        let root = cstring(root)?;
+        let db_path = cstring(db_path)?;
 
         let handle = unsafe { alpm_initialize(root.as_ptr(), db_path.as_ptr(), &mut err) };
 
@@ -57,6 +81,8 @@ impl Alpm {
         }
     }
 
+    // The errno is read here rather than when the error is used, as any later call on the
+    // handle may overwrite it.
     pub(crate) fn check_ret(&self, int: c_int) -> Result<()> {
         if int != 0 {
             Err(self.last_error())
@@ -74,11 +100,84 @@ impl Alpm {
     }
 }
 
+/// A libalpm handle that can be moved to another thread.
+///
+/// libalpm is not thread safe, but a handle carries no thread-local state, so it can be used
+/// from any one thread at a time. `SendAlpm` is `Send` but not `Sync`: to share a handle,
+/// wrap it in a `Mutex`. Packages, databases and lists borrow from the handle and stay on the
+/// thread that holds the lock.
+///
+/// Callbacks must be set through the `set_*_cb` methods of `SendAlpm`, which require the
+/// callback and its data to be `Send`. Setting a callback through the inner [`Alpm`], raw
+/// callbacks included, panics. Callbacks are run on whichever thread is driving the handle.
+///
+/// ```compile_fail
+/// # use std::rc::Rc;
+/// let handle = alpm::SendAlpm::new("/", "tests/db").unwrap();
+/// handle.set_log_cb(Rc::new(()), |_, _, _| ());
+/// ```
+pub struct SendAlpm(pub(crate) Alpm);
+
+unsafe impl Send for SendAlpm {}
+
+impl std::fmt::Debug for SendAlpm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::ops::Deref for SendAlpm {
+    type Target = Alpm;
+
+    fn deref(&self) -> &Alpm {
+        &self.0
+    }
+}
+
+impl SendAlpm {
+    pub fn new<S: Into<Vec<u8>>>(root: S, db_path: S) -> Result<SendAlpm> {
+        let handle = Alpm::new(root, db_path)?;
+        handle.cbs.send.set(true);
+        Ok(SendAlpm(handle))
+    }
+
+    /// Runs `f` with mutable access to the handle.
+    ///
+    /// If `f` replaces the handle, the callbacks of the new handle are dropped.
+    pub fn with_mut<R, F: FnOnce(&mut Alpm) -> R>(&mut self, f: F) -> R {
+        let ret = f(&mut self.0);
+        if !self.0.cbs.send.get() {
+            self.0.clear_callbacks();
+            self.0.cbs.send.set(true);
+        }
+        ret
+    }
+
+    /// Unwraps the handle, keeping its callbacks.
+    pub fn into_inner(self) -> Alpm {
+        self.0.cbs.send.set(false);
+        self.0
+    }
+}
+
+/// The version of the linked libalpm.
 pub fn version() -> &'static str {
     unsafe { from_cstr(alpm_version()) }
 }
 
+/// The features the linked libalpm was built with. Same as [`Capabilities::new`].
+pub fn capabilities() -> Capabilities {
+    Capabilities::new()
+}
+
 bitflags! {
+    /// The optional features libalpm was built with.
+    ///
+    /// These are checked at runtime against the linked library and are independent of this
+    /// crate's cargo features. The `git` feature only decides which functions are bound,
+    /// a library built without signature support still accepts [`SigLevel`](crate::SigLevel)
+    /// but fails to verify anything, so check [`has_signatures`](Capabilities::has_signatures)
+    /// before requiring signatures.
     pub struct Capabilities: u32 {
         const NLS = alpm_caps::ALPM_CAPABILITY_NLS;
         const DOWNLOADER = alpm_caps::ALPM_CAPABILITY_DOWNLOADER;
@@ -94,7 +193,28 @@ impl Default for Capabilities {
 
 impl Capabilities {
     pub fn new() -> Capabilities {
-        Capabilities::from_bits(unsafe { alpm_capabilities() as u32 }).unwrap()
+        unsafe { Capabilities::from_bits_unchecked(alpm_capabilities() as u32) }
+    }
+
+    /// The capabilities of the linked libalpm. Same as [`Capabilities::new`].
+    pub fn current() -> Capabilities {
+        Capabilities::new()
+    }
+
+    /// Whether libalpm was built with translations.
+    pub fn has_nls(self) -> bool {
+        self.nls()
+    }
+
+    /// Whether libalpm was built with its own downloader. Without it a
+    /// [fetch callback](crate::Alpm::set_fetch_cb) must be set to download anything.
+    pub fn has_downloader(self) -> bool {
+        self.downloader()
+    }
+
+    /// Whether libalpm was built with gpgme and can verify signatures.
+    pub fn has_signatures(self) -> bool {
+        self.signatures()
     }
 
     pub fn nls(self) -> bool {
@@ -114,6 +234,110 @@ impl Capabilities {
 mod tests {
     use super::*;
     use crate::SigLevel;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    #[test]
+    fn test_version_capabilities() {
+        let parts = version().split('.').collect::<Vec<_>>();
+        assert!(parts.len() >= 2);
+        assert!(parts.iter().all(|p| p.parse::<u32>().is_ok()));
+
+        let caps = capabilities();
+        assert!(!caps.is_empty());
+        assert_eq!(caps, Capabilities::default());
+        assert_eq!(caps, Capabilities::current());
+        assert_eq!(
+            caps.has_signatures(),
+            caps.contains(Capabilities::SIGNATURES)
+        );
+        assert_eq!(
+            caps.has_downloader(),
+            caps.contains(Capabilities::DOWNLOADER)
+        );
+        assert_eq!(caps.has_nls(), caps.contains(Capabilities::NLS));
+        assert!(!Capabilities::empty().has_signatures());
+    }
+
+    #[test]
+    fn test_debug() {
+        let mut handle = Alpm::new("/", "tests/db").unwrap();
+        for i in 0..12 {
+            handle.add_ignorepkg(format!("pkg{}", i)).unwrap();
+        }
+        let core = handle.register_syncdb_mut("core", SigLevel::NONE).unwrap();
+        core.add_server("https://example.com/core").unwrap();
+        core.add_server("https://example.org/core").unwrap();
+
+        let debug = format!("{:?}", handle);
+        assert!(debug.starts_with("Alpm { root: \"/\", dbpath: "));
+        assert!(debug.contains("tests/db/\", lockfile: "));
+        assert!(debug.contains("syncdbs: [(\"core\", 2)]"));
+        assert!(debug.contains("\"pkg9\", ... 2 more]"));
+        assert!(!debug.contains("pkg10"));
+        assert!(debug.contains("parallel_downloads: 1"));
+
+        let core = handle.syncdbs().first().unwrap();
+        assert_eq!(
+            format!("{:?}", core),
+            format!(
+                "Db {{ name: \"core\", pkgs: {}, servers: [\"https://example.com/core\", \"https://example.org/core\"] }}",
+                core.pkgs().len()
+            )
+        );
+    }
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<SendAlpm>();
+        assert_send::<Arc<Mutex<SendAlpm>>>();
+
+        let handle = SendAlpm::new("/", "tests/db").unwrap();
+        let logged = Arc::new(Mutex::new(Vec::new()));
+        handle.set_log_cb(Arc::clone(&logged), |_, msg, logged| {
+            logged.lock().unwrap().push(msg.to_string())
+        });
+
+        let handle = Arc::new(Mutex::new(handle));
+        let worker = {
+            let handle = Arc::clone(&handle);
+            thread::spawn(move || {
+                let handle = handle.lock().unwrap();
+                handle.register_syncdb("core", SigLevel::NONE).unwrap();
+                handle.localdb().pkgs().len()
+            })
+        };
+        let count = worker.join().unwrap();
+        assert_eq!(count, handle.lock().unwrap().localdb().pkgs().len());
+        assert!(!logged.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "callbacks of a SendAlpm must be set through SendAlpm")]
+    fn test_send_deref_cb() {
+        let handle = SendAlpm::new("/", "tests/db").unwrap();
+        let handle: &Alpm = &handle;
+        handle.set_log_cb(std::rc::Rc::new(()), |_, _, _| ());
+    }
+
+    #[test]
+    fn test_send_with_mut() {
+        let mut handle = SendAlpm::new("/", "tests/db").unwrap();
+        handle.with_mut(|handle| handle.add_ignorepkg("linux").unwrap());
+        assert_eq!(handle.ignorepkgs().iter().collect::<Vec<_>>(), ["linux"]);
+
+        let replaced = handle.with_mut(|handle| {
+            let other = Alpm::new("/", "tests/db").unwrap();
+            other.set_log_cb(std::rc::Rc::new(()), |_, _, _| ());
+            std::mem::replace(handle, other)
+        });
+        drop(replaced);
+        assert!(handle.take_raw_log_cb().cb.is_none());
+
+        let handle = handle.into_inner();
+        handle.set_log_cb(std::rc::Rc::new(()), |_, _, _| ());
+    }
 
     #[test]
     fn test_lifetime() {
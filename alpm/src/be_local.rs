@@ -1,13 +1,99 @@
-use crate::{Package, PackageReason, Result};
+use crate::{Alpm, Package, PackageReason, Pkg, Result, Ver};
 
 use alpm_sys::*;
 
 use std::mem::transmute;
 
 impl<'a> Package<'a> {
+    /// Changes the install reason of a package in the local database.
+    ///
+    /// This writes to the local database directly, so the caller should hold
+    /// the database lock (e.g. by having a transaction initialized) while
+    /// calling this. Fails with [`Error::WrongArgs`](crate::Error::WrongArgs)
+    /// if the package does not come from the local database.
+    #[doc(alias = "set_pkg_reason")]
     pub fn set_reason(&mut self, reason: PackageReason) -> Result<()> {
         let reason = unsafe { transmute::<PackageReason, _alpm_pkgreason_t>(reason) };
         let ret = unsafe { alpm_pkg_set_reason(self.pkg.pkg, reason) };
         self.handle.check_ret(ret)
     }
 }
+
+impl Alpm {
+    /// Whether a package called `name` is in the local database. Provides are not
+    /// considered.
+    pub fn is_installed<S: Into<Vec<u8>>>(&self, name: S) -> bool {
+        self.localdb().pkg(name).is_ok()
+    }
+}
+
+impl<'a> Pkg<'a> {
+    /// The version of the package of the same name in the local database, if it is
+    /// installed.
+    pub fn installed_version(&self) -> Option<&'a Ver> {
+        let pkg = self.handle.localdb().pkg(self.name()).ok()?;
+        Some(pkg.version())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::TempDir;
+    use crate::{Alpm, Error, PackageReason, SigLevel};
+
+    #[test]
+    fn test_set_reason() {
+        let tmp = TempDir::new("set-reason");
+        let dbpath = tmp.local_db(&["vifm-0.10-1"]);
+
+        let handle = Alpm::new("/", dbpath.to_str().unwrap()).unwrap();
+        let mut pkg = handle.localdb().pkg("vifm").unwrap();
+        assert_eq!(pkg.reason(), PackageReason::Explicit);
+        pkg.set_reason(PackageReason::Depend).unwrap();
+        assert_eq!(pkg.reason(), PackageReason::Depend);
+        drop(handle);
+
+        let handle = Alpm::new("/", dbpath.to_str().unwrap()).unwrap();
+        let mut pkg = handle.localdb().pkg("vifm").unwrap();
+        assert_eq!(pkg.reason(), PackageReason::Depend);
+        pkg.set_reason(PackageReason::Explicit).unwrap();
+        drop(handle);
+
+        let handle = Alpm::new("/", dbpath.to_str().unwrap()).unwrap();
+        let pkg = handle.localdb().pkg("vifm").unwrap();
+        assert_eq!(pkg.reason(), PackageReason::Explicit);
+    }
+
+    #[test]
+    fn test_set_reason_sync() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let db = handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        let mut pkg = db.pkg("linux").unwrap();
+        assert_eq!(pkg.set_reason(PackageReason::Depend), Err(Error::WrongArgs));
+    }
+
+    #[test]
+    fn test_installed_version() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let core = handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        let community = handle.register_syncdb("community", SigLevel::NONE).unwrap();
+
+        assert!(handle.is_installed("bash"));
+        assert!(!handle.is_installed("sh"));
+        assert!(!handle.is_installed("not-a-package"));
+
+        let bash = core.pkg("bash").unwrap();
+        assert_eq!(bash.version().as_str(), "5.0.007-1");
+        assert_eq!(bash.installed_version().unwrap().as_str(), "5.0.002-1");
+
+        let local = handle.localdb().pkg("bash").unwrap();
+        assert_eq!(local.installed_version(), Some(local.version()));
+
+        let missing = community
+            .pkgs()
+            .iter()
+            .find(|p| !handle.is_installed(p.name()))
+            .unwrap();
+        assert_eq!(missing.installed_version(), None);
+    }
+}
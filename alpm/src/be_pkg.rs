@@ -1,14 +1,19 @@
-use crate::{Alpm, AsPkg, Pkg, Result, SigLevel};
+use crate::utils::*;
+use crate::{Alpm, AsPkg, FileList, Pkg, Result, SigLevel};
 
 use alpm_sys::*;
 
-use std::ffi::CString;
 use std::os::raw::c_int;
 use std::ptr;
 
+/// A package loaded from a file with [`Alpm::pkg_load`].
+///
+/// The package is freed on drop unless it is passed to
+/// [`trans_add_pkg`](Alpm::trans_add_pkg), in which case the transaction takes ownership.
 #[derive(Debug)]
 pub struct LoadedPackage<'a> {
     pub(crate) pkg: Pkg<'a>,
+    pub(crate) full: bool,
 }
 
 impl<'a> Drop for LoadedPackage<'a> {
@@ -37,16 +42,38 @@ impl<'a> LoadedPackage<'a> {
     pub fn pkg(&'a self) -> Pkg<'a> {
         self.pkg
     }
+
+    /// Whether the package was loaded with `full` set, so its file list was read.
+    ///
+    /// Unlike [`Pkg::has_full_data`] this knows how the package was loaded.
+    pub fn has_full_data(&self) -> bool {
+        self.full
+    }
+
+    /// Like [`files`](Pkg::files) but returns `None` if the package was loaded without
+    /// `full`.
+    pub fn try_files(&self) -> Option<FileList<'a>> {
+        if self.full {
+            Some(self.pkg.files())
+        } else {
+            None
+        }
+    }
 }
 
 impl Alpm {
+    /// Loads a package from a file.
+    ///
+    /// If `full` is false only the metadata is read and the file list is left empty. The
+    /// package signature is checked according to `level` and a failed check is returned as
+    /// an error.
     pub fn pkg_load<S: Into<Vec<u8>>>(
         &self,
         filename: S,
         full: bool,
         level: SigLevel,
     ) -> Result<LoadedPackage> {
-        let filename = CString::new(filename).unwrap();
+        let filename = cstring(filename)?;
         let mut pkg = Pkg {
             pkg: ptr::null_mut(),
             handle: self,
@@ -62,14 +89,14 @@ impl Alpm {
             )
         };
         self.check_ret(ret)?;
-        Ok(LoadedPackage { pkg })
+        Ok(LoadedPackage { pkg, full })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::Result;
+    use crate::{Result, TransFlag};
 
     #[test]
     fn load() -> Result<()> {
@@ -117,4 +144,32 @@ mod tests {
         assert_eq!(pkg.sha256sum(), None);
         assert_eq!(pkg.base64_sig(), None);
     }
+
+    #[test]
+    fn load_required_sig() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let pkg = handle.pkg_load(
+            "tests/pacman-5.1.3-1-x86_64.pkg.tar.xz",
+            false,
+            SigLevel::PACKAGE,
+        );
+        assert!(pkg.is_err());
+    }
+
+    #[test]
+    fn load_add_to_trans() {
+        let mut handle = Alpm::new("/", "tests/db").unwrap();
+        let pkg = handle
+            .pkg_load(
+                "tests/pacman-5.1.3-1-x86_64.pkg.tar.xz",
+                true,
+                SigLevel::NONE,
+            )
+            .unwrap();
+
+        handle.trans_init(TransFlag::NO_LOCK).unwrap();
+        handle.trans_add_pkg(pkg).unwrap();
+        assert_eq!(handle.trans_add().first().unwrap().name(), "pacman");
+        handle.trans_release().unwrap();
+    }
 }
@@ -1,6 +1,9 @@
-use crate::{free, Alpm, AnyDownloadEvent, AnyEvent, AnyQuestion, FetchResult, LogLevel, Progress};
+use crate::utils::from_cstr;
+use crate::{
+    free, Alpm, AnyDownloadEvent, AnyEvent, AnyQuestion, FetchResult, LogLevel, Progress, SendAlpm,
+};
 use alpm_sys::*;
-use std::cell::{RefCell, UnsafeCell};
+use std::cell::{Cell, RefCell, UnsafeCell};
 use std::ffi::{c_void, CStr};
 use std::mem::transmute;
 use std::os::raw::{c_char, c_int};
@@ -20,6 +23,17 @@ pub(crate) struct Callbacks {
     pub(crate) progress: Cb<dyn ProgressCbTrait>,
     pub(crate) question: Cb<dyn QuestionCbTrait>,
     pub(crate) fetch: Cb<dyn FetchCbTrait>,
+    // Set while the handle is owned by a SendAlpm, which only accepts Send callbacks.
+    pub(crate) send: Cell<bool>,
+}
+
+impl Callbacks {
+    fn assert_not_send(&self) {
+        assert!(
+            !self.send.get(),
+            "callbacks of a SendAlpm must be set through SendAlpm"
+        );
+    }
 }
 
 pub(crate) trait LogCbTrait {
@@ -228,6 +242,72 @@ impl Alpm {
         &self,
         data: T,
         f: F,
+    ) {
+        self.cbs.assert_not_send();
+        self.set_log_cb_unchecked(data, f)
+    }
+
+    pub fn set_dl_cb<T: 'static, F: FnMut(&str, AnyDownloadEvent, &mut T) + 'static>(
+        &self,
+        data: T,
+        f: F,
+    ) {
+        self.cbs.assert_not_send();
+        self.set_dl_cb_unchecked(data, f)
+    }
+
+    pub fn set_event_cb<T: 'static, F: FnMut(AnyEvent, &mut T) + 'static>(&self, data: T, f: F) {
+        self.cbs.assert_not_send();
+        self.set_event_cb_unchecked(data, f)
+    }
+
+    pub fn set_progress_cb<
+        T: 'static,
+        F: FnMut(Progress, &str, i32, usize, usize, &mut T) + 'static,
+    >(
+        &self,
+        data: T,
+        f: F,
+    ) {
+        self.cbs.assert_not_send();
+        self.set_progress_cb_unchecked(data, f)
+    }
+
+    pub fn set_question_cb<T: 'static, F: FnMut(AnyQuestion, &mut T) + 'static>(
+        &self,
+        data: T,
+        f: F,
+    ) {
+        self.cbs.assert_not_send();
+        self.set_question_cb_unchecked(data, f)
+    }
+
+    /// Sets the callback used to download files instead of libalpm's downloader.
+    ///
+    /// Downloads whose url or destination path is not valid UTF-8 fail without calling the
+    /// callback.
+    pub fn set_fetch_cb<T: 'static, F: FnMut(&str, &str, bool, &mut T) -> FetchResult + 'static>(
+        &self,
+        data: T,
+        f: F,
+    ) {
+        self.cbs.assert_not_send();
+        self.set_fetch_cb_unchecked(data, f)
+    }
+
+    pub(crate) fn clear_callbacks(&self) {
+        drop(self.take_raw_log_cb());
+        drop(self.take_raw_dl_cb());
+        drop(self.take_raw_event_cb());
+        drop(self.take_raw_progress_cb());
+        drop(self.take_raw_question_cb());
+        drop(self.take_raw_fetch_cb());
+    }
+
+    fn set_log_cb_unchecked<T: 'static, F: FnMut(LogLevel, &str, &mut T) + 'static>(
+        &self,
+        data: T,
+        f: F,
     ) {
         let c = unsafe { &mut *self.cbs.log.get() };
         if let Some(cb) = c.as_ref() {
@@ -240,7 +320,7 @@ impl Alpm {
         c.replace(ctx);
     }
 
-    pub fn set_dl_cb<T: 'static, F: FnMut(&str, AnyDownloadEvent, &mut T) + 'static>(
+    fn set_dl_cb_unchecked<T: 'static, F: FnMut(&str, AnyDownloadEvent, &mut T) + 'static>(
         &self,
         data: T,
         f: F,
@@ -260,7 +340,11 @@ impl Alpm {
         c.replace(ctx);
     }
 
-    pub fn set_event_cb<T: 'static, F: FnMut(AnyEvent, &mut T) + 'static>(&self, data: T, f: F) {
+    fn set_event_cb_unchecked<T: 'static, F: FnMut(AnyEvent, &mut T) + 'static>(
+        &self,
+        data: T,
+        f: F,
+    ) {
         let c = unsafe { &mut *self.cbs.event.get() };
         if let Some(cb) = c.as_ref() {
             cb.assert_unlocked()
@@ -272,7 +356,7 @@ impl Alpm {
         c.replace(ctx);
     }
 
-    pub fn set_progress_cb<
+    fn set_progress_cb_unchecked<
         T: 'static,
         F: FnMut(Progress, &str, i32, usize, usize, &mut T) + 'static,
     >(
@@ -291,7 +375,7 @@ impl Alpm {
         c.replace(ctx);
     }
 
-    pub fn set_question_cb<T: 'static, F: FnMut(AnyQuestion, &mut T) + 'static>(
+    fn set_question_cb_unchecked<T: 'static, F: FnMut(AnyQuestion, &mut T) + 'static>(
         &self,
         data: T,
         f: F,
@@ -307,7 +391,10 @@ impl Alpm {
         c.replace(ctx);
     }
 
-    pub fn set_fetch_cb<T: 'static, F: FnMut(&str, &str, bool, &mut T) -> FetchResult + 'static>(
+    fn set_fetch_cb_unchecked<
+        T: 'static,
+        F: FnMut(&str, &str, bool, &mut T) -> FetchResult + 'static,
+    >(
         &self,
         data: T,
         f: F,
@@ -339,6 +426,7 @@ impl Alpm {
     }
 
     pub fn set_raw_log_cb(&self, cb: RawLogCb) {
+        self.cbs.assert_not_send();
         let c = unsafe { &mut *self.cbs.log.get() };
         if let Some(cb) = c.as_ref() {
             cb.assert_unlocked()
@@ -362,6 +450,7 @@ impl Alpm {
     }
 
     pub fn set_raw_dl_cb(&self, cb: RawDlCb) {
+        self.cbs.assert_not_send();
         let c = unsafe { &mut *self.cbs.dl.get() };
         if let Some(cb) = c.as_ref() {
             cb.assert_unlocked()
@@ -385,6 +474,7 @@ impl Alpm {
     }
 
     pub fn set_raw_event_cb(&self, cb: RawEventCb) {
+        self.cbs.assert_not_send();
         let c = unsafe { &mut *self.cbs.event.get() };
         if let Some(cb) = c.as_ref() {
             cb.assert_unlocked()
@@ -410,6 +500,7 @@ impl Alpm {
     }
 
     pub fn set_raw_progress_cb(&self, cb: RawProgressCb) {
+        self.cbs.assert_not_send();
         let c = unsafe { &mut *self.cbs.progress.get() };
         if let Some(cb) = c.as_ref() {
             cb.assert_unlocked()
@@ -435,6 +526,7 @@ impl Alpm {
     }
 
     pub fn set_raw_question_cb(&self, cb: RawQuestionCb) {
+        self.cbs.assert_not_send();
         let c = unsafe { &mut *self.cbs.question.get() };
         if let Some(cb) = c.as_ref() {
             cb.assert_unlocked()
@@ -461,6 +553,7 @@ impl Alpm {
     }
 
     pub fn set_raw_fetch_cb(&self, cb: RawFetchCb) {
+        self.cbs.assert_not_send();
         let c = unsafe { &mut *self.cbs.fetch.get() };
         if let Some(cb) = c.as_ref() {
             cb.assert_unlocked()
@@ -471,6 +564,66 @@ impl Alpm {
     }
 }
 
+impl SendAlpm {
+    pub fn set_log_cb<T: Send + 'static, F: FnMut(LogLevel, &str, &mut T) + Send + 'static>(
+        &self,
+        data: T,
+        f: F,
+    ) {
+        self.0.set_log_cb_unchecked(data, f)
+    }
+
+    pub fn set_dl_cb<
+        T: Send + 'static,
+        F: FnMut(&str, AnyDownloadEvent, &mut T) + Send + 'static,
+    >(
+        &self,
+        data: T,
+        f: F,
+    ) {
+        self.0.set_dl_cb_unchecked(data, f)
+    }
+
+    pub fn set_event_cb<T: Send + 'static, F: FnMut(AnyEvent, &mut T) + Send + 'static>(
+        &self,
+        data: T,
+        f: F,
+    ) {
+        self.0.set_event_cb_unchecked(data, f)
+    }
+
+    pub fn set_progress_cb<
+        T: Send + 'static,
+        F: FnMut(Progress, &str, i32, usize, usize, &mut T) + Send + 'static,
+    >(
+        &self,
+        data: T,
+        f: F,
+    ) {
+        self.0.set_progress_cb_unchecked(data, f)
+    }
+
+    pub fn set_question_cb<T: Send + 'static, F: FnMut(AnyQuestion, &mut T) + Send + 'static>(
+        &self,
+        data: T,
+        f: F,
+    ) {
+        self.0.set_question_cb_unchecked(data, f)
+    }
+
+    /// Same as [`Alpm::set_fetch_cb`].
+    pub fn set_fetch_cb<
+        T: Send + 'static,
+        F: FnMut(&str, &str, bool, &mut T) -> FetchResult + Send + 'static,
+    >(
+        &self,
+        data: T,
+        f: F,
+    ) {
+        self.0.set_fetch_cb_unchecked(data, f)
+    }
+}
+
 extern "C" fn logcb<C: LogCbTrait>(
     ctx: *mut c_void,
     level: alpm_loglevel_t,
@@ -482,7 +635,7 @@ extern "C" fn logcb<C: LogCbTrait>(
     if n != -1 {
         let _ = panic::catch_unwind(|| {
             let s = unsafe { CStr::from_ptr(buff) };
-            let level = LogLevel::from_bits(level).unwrap();
+            let level = unsafe { LogLevel::from_bits_unchecked(level) };
             let cb = unsafe { &*(ctx as *const C) };
             cb.call(level, &s.to_string_lossy());
         });
@@ -498,8 +651,7 @@ extern "C" fn dlcb<C: DlCbTrait>(
     data: *mut c_void,
 ) {
     let _ = panic::catch_unwind(|| {
-        let filename = unsafe { CStr::from_ptr(filename) };
-        let filename = filename.to_str().unwrap();
+        let filename = unsafe { from_cstr(filename) };
         let event = unsafe { AnyDownloadEvent::new(event, data) };
         let cb = unsafe { &*(ctx as *const C) };
         cb.call(filename, event);
@@ -513,8 +665,14 @@ extern "C" fn fetchcb<C: FetchCbTrait>(
     force: c_int,
 ) -> c_int {
     let ret = panic::catch_unwind(|| {
-        let url = unsafe { CStr::from_ptr(url).to_str().unwrap() };
-        let localpath = unsafe { CStr::from_ptr(localpath).to_str().unwrap() };
+        // Cutting a url or path short would fetch or write the wrong file, so ones that
+        // are not valid UTF-8 fail instead.
+        let url = unsafe { CStr::from_ptr(url).to_str() };
+        let localpath = unsafe { CStr::from_ptr(localpath).to_str() };
+        let (url, localpath) = match (url, localpath) {
+            (Ok(url), Ok(localpath)) => (url, localpath),
+            _ => return -1,
+        };
         let cb = unsafe { &*(ctx as *const C) };
         let ret = cb.call(url, localpath, force != 0);
 
@@ -554,8 +712,7 @@ extern "C" fn progresscb<C: ProgressCbTrait>(
     current: usize,
 ) {
     let _ = panic::catch_unwind(|| {
-        let pkgname = unsafe { CStr::from_ptr(pkgname) };
-        let pkgname = pkgname.to_str().unwrap();
+        let pkgname = unsafe { from_cstr(pkgname) };
         let progress = unsafe { transmute::<alpm_progress_t, Progress>(progress) };
         let cb = unsafe { &*(ctx as *const C) };
         cb.call(progress, pkgname, percent as i32, howmany, current);
@@ -0,0 +1,226 @@
+use crate::{Alpm, Depend, Result, SigLevel, Usage};
+
+/// An owned copy of a handle's configuration, see [`Alpm::config_snapshot`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct HandleConfig {
+    pub root: String,
+    pub dbpath: String,
+    pub lockfile: String,
+    pub dbext: String,
+    pub cachedirs: Vec<String>,
+    pub hookdirs: Vec<String>,
+    pub gpgdir: String,
+    pub logfile: Option<String>,
+    pub use_syslog: bool,
+    pub check_space: bool,
+    pub architectures: Vec<String>,
+    pub ignorepkgs: Vec<String>,
+    pub ignoregroups: Vec<String>,
+    pub noupgrades: Vec<String>,
+    pub noextracts: Vec<String>,
+    pub overwrite_files: Vec<String>,
+    pub assume_installed: Vec<String>,
+    pub default_siglevel: SigLevel,
+    pub local_file_siglevel: SigLevel,
+    pub remote_file_siglevel: SigLevel,
+    pub parallel_downloads: u32,
+    #[cfg(feature = "git")]
+    pub sandbox_user: Option<String>,
+    #[cfg(feature = "git")]
+    pub disable_sandbox: bool,
+    pub syncdbs: Vec<SyncDbConfig>,
+}
+
+/// The configuration of a registered sync database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SyncDbConfig {
+    pub name: String,
+    pub servers: Vec<String>,
+    pub siglevel: SigLevel,
+    pub usage: Usage,
+}
+
+impl Alpm {
+    /// Copies every option the handle is configured with, along with the registered sync
+    /// databases, into a [`HandleConfig`].
+    pub fn config_snapshot(&self) -> HandleConfig {
+        let strings = |list: crate::AlpmList<&str>| list.iter().map(|s| s.to_string()).collect();
+
+        let syncdbs = self
+            .syncdbs()
+            .iter()
+            .map(|db| SyncDbConfig {
+                name: db.name().to_string(),
+                servers: strings(db.servers()),
+                siglevel: db.siglevel(),
+                usage: db.usage().unwrap_or(Usage::ALL),
+            })
+            .collect();
+
+        HandleConfig {
+            root: self.root().to_string(),
+            dbpath: self.dbpath().to_string(),
+            lockfile: self.lockfile().to_string(),
+            dbext: self.dbext().to_string(),
+            cachedirs: strings(self.cachedirs()),
+            hookdirs: strings(self.hookdirs()),
+            gpgdir: self.gpgdir().to_string(),
+            logfile: self.logfile().map(|s| s.to_string()),
+            use_syslog: self.use_syslog(),
+            check_space: self.check_space(),
+            architectures: strings(self.architectures()),
+            ignorepkgs: strings(self.ignorepkgs()),
+            ignoregroups: strings(self.ignoregroups()),
+            noupgrades: strings(self.noupgrades()),
+            noextracts: strings(self.noextracts()),
+            overwrite_files: strings(self.overwrite_files()),
+            assume_installed: self
+                .assume_installed()
+                .iter()
+                .map(|d| d.to_string())
+                .collect(),
+            default_siglevel: self.default_siglevel(),
+            local_file_siglevel: self.local_file_siglevel(),
+            remote_file_siglevel: self.remote_file_siglevel(),
+            parallel_downloads: self.parallel_downloads(),
+            #[cfg(feature = "git")]
+            sandbox_user: self.sandbox_user().map(|s| s.to_string()),
+            #[cfg(feature = "git")]
+            disable_sandbox: self.disable_sandbox(),
+            syncdbs,
+        }
+    }
+}
+
+impl HandleConfig {
+    /// Applies the configuration to `handle`.
+    ///
+    /// The root, dbpath and lockfile are fixed when a handle is created so they are not
+    /// changed; create the handle with [`Alpm::new`] using [`root`](HandleConfig::root) and
+    /// [`dbpath`](HandleConfig::dbpath) first. Any sync databases registered on `handle`
+    /// are unregistered and replaced with [`syncdbs`](HandleConfig::syncdbs).
+    ///
+    /// libalpm can not unset the gpgdir or the logfile, so an empty
+    /// [`gpgdir`](HandleConfig::gpgdir) or a `None` [`logfile`](HandleConfig::logfile)
+    /// leaves the one `handle` already has. Apply to a new handle to get an exact copy.
+    pub fn apply(&self, handle: &mut Alpm) -> Result<()> {
+        handle.set_dbext(self.dbext.as_str())?;
+        handle.set_cachedirs(self.cachedirs.iter())?;
+        handle.set_hookdirs(self.hookdirs.iter())?;
+        if !self.gpgdir.is_empty() {
+            handle.set_gpgdir(self.gpgdir.as_str())?;
+        }
+        if let Some(logfile) = &self.logfile {
+            handle.set_logfile(logfile.as_str())?;
+        }
+        handle.set_use_syslog(self.use_syslog);
+        handle.set_check_space(self.check_space);
+        handle.set_architectures(self.architectures.iter())?;
+        handle.set_ignorepkgs(self.ignorepkgs.iter())?;
+        handle.set_ignoregroups(self.ignoregroups.iter())?;
+        handle.set_noupgrades(self.noupgrades.iter())?;
+        handle.set_noextracts(self.noextracts.iter())?;
+        handle.set_overwrite_files(self.overwrite_files.iter())?;
+        let assume_installed = self
+            .assume_installed
+            .iter()
+            .map(|d| Depend::try_new(d.as_str()))
+            .collect::<Result<Vec<_>>>()?;
+        handle.set_assume_installed(assume_installed.iter())?;
+        handle.set_default_siglevel(self.default_siglevel)?;
+        handle.set_local_file_siglevel(self.local_file_siglevel)?;
+        handle.set_remote_file_siglevel(self.remote_file_siglevel)?;
+        handle.set_parallel_downloads(self.parallel_downloads);
+        #[cfg(feature = "git")]
+        {
+            handle.set_sandbox_user(self.sandbox_user.as_deref())?;
+            handle.set_disable_sandbox(self.disable_sandbox)?;
+        }
+
+        handle.unregister_all_syncdbs()?;
+        for db in &self.syncdbs {
+            let new = handle.register_syncdb_mut(db.name.as_str(), db.siglevel)?;
+            new.set_servers(db.servers.iter())?;
+            new.set_usage(db.usage)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_snapshot() {
+        let mut handle = Alpm::new("/", "tests/db").unwrap();
+        handle.add_cachedir("/var/cache/pacman/pkg").unwrap();
+        handle.add_ignorepkg("linux").unwrap();
+        handle.add_ignoregroup("gnome").unwrap();
+        handle.add_noupgrade("etc/pacman.conf").unwrap();
+        handle.add_noextract("usr/share/doc/*").unwrap();
+        handle.add_overwrite_file("usr/lib/foo").unwrap();
+        handle.add_architecture("x86_64").unwrap();
+        handle.add_assume_installed(Depend::new("foo>=1")).unwrap();
+        handle.set_check_space(true);
+        handle.set_parallel_downloads(4);
+        handle
+            .set_default_siglevel(SigLevel::PACKAGE | SigLevel::DATABASE_OPTIONAL)
+            .unwrap();
+        let core = handle.register_syncdb_mut("core", SigLevel::NONE).unwrap();
+        core.add_server("https://example.com/core").unwrap();
+        core.set_usage(Usage::SYNC | Usage::SEARCH).unwrap();
+        handle
+            .register_syncdb_mut("extra", SigLevel::USE_DEFAULT)
+            .unwrap();
+
+        let config = handle.config_snapshot();
+        assert_eq!(config.root, "/");
+        assert_eq!(config.ignorepkgs, ["linux"]);
+        assert_eq!(config.assume_installed, ["foo>=1"]);
+        assert_eq!(config.parallel_downloads, 4);
+        assert!(config.check_space);
+        assert_eq!(config.syncdbs.len(), 2);
+        assert_eq!(config.syncdbs[0].name, "core");
+        assert_eq!(config.syncdbs[0].servers, ["https://example.com/core"]);
+        assert_eq!(config.syncdbs[0].usage, Usage::SYNC | Usage::SEARCH);
+        assert_eq!(config.syncdbs[1].siglevel, config.default_siglevel);
+
+        let mut other = Alpm::new(config.root.as_str(), config.dbpath.as_str()).unwrap();
+        other.register_syncdb("testing", SigLevel::NONE).unwrap();
+        config.apply(&mut other).unwrap();
+        assert_eq!(other.config_snapshot(), config);
+        assert!(other.syncdbs().iter().all(|db| db.name() != "testing"));
+    }
+
+    #[test]
+    fn test_config_apply_unset() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let config = handle.config_snapshot();
+        assert_eq!(config.gpgdir, "");
+        assert_eq!(config.logfile, None);
+
+        let mut other = Alpm::new("/", "tests/db").unwrap();
+        other.set_gpgdir("/etc/pacman.d/gnupg/").unwrap();
+        other.set_logfile("/var/log/pacman.log").unwrap();
+        other.add_ignorepkg("linux").unwrap();
+        config.apply(&mut other).unwrap();
+
+        // The other options are reset but the gpgdir and logfile can't be.
+        let applied = other.config_snapshot();
+        assert!(applied.ignorepkgs.is_empty());
+        assert_eq!(applied.gpgdir, "/etc/pacman.d/gnupg/");
+        assert_eq!(applied.logfile.as_deref(), Some("/var/log/pacman.log"));
+        assert_eq!(
+            HandleConfig {
+                gpgdir: String::new(),
+                logfile: None,
+                ..applied
+            },
+            config
+        );
+    }
+}
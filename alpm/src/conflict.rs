@@ -1,12 +1,16 @@
+use crate::list::{dup_bytes, dup_str, dup_struct};
 use crate::utils::*;
-use crate::{Alpm, AlpmListMut, AsAlpmListItemPtr, AsPkg, Dep, IntoRawAlpmList};
+use crate::{Alpm, AlpmListMut, AsAlpmListItemPtr, AsPkg, Dep, IntoRawAlpmList, Pkg};
 
 use alpm_sys::alpm_fileconflicttype_t::*;
 use alpm_sys::*;
 
+use std::collections::HashMap;
 use std::fmt;
 use std::marker::PhantomData;
 use std::mem::transmute;
+use std::os::raw::c_void;
+use std::ptr;
 
 pub struct OwnedConflict {
     conflict: Conflict<'static>,
@@ -46,6 +50,34 @@ impl<'a> fmt::Debug for Conflict<'a> {
     }
 }
 
+impl fmt::Display for OwnedConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.conflict, f)
+    }
+}
+
+impl<'a> fmt::Display for Conflict<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = self.reason();
+        if reason.name() == self.package1() || reason.name() == self.package2() {
+            write!(
+                f,
+                "{} and {} are in conflict",
+                self.package1(),
+                self.package2()
+            )
+        } else {
+            write!(
+                f,
+                "{} and {} are in conflict ({})",
+                self.package1(),
+                self.package2(),
+                reason
+            )
+        }
+    }
+}
+
 impl std::ops::Deref for OwnedConflict {
     type Target = Conflict<'static>;
 
@@ -96,7 +128,7 @@ impl<'a> Conflict<'a> {
 }
 
 #[repr(u32)]
-#[derive(Debug)]
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Ord, PartialOrd, Hash)]
 pub enum FileConflictType {
     Target = ALPM_FILECONFLICT_TARGET as u32,
     Filesystem = ALPM_FILECONFLICT_FILESYSTEM as u32,
@@ -118,6 +150,30 @@ impl<'a> fmt::Debug for FileConflict<'a> {
     }
 }
 
+impl<'a> fmt::Display for FileConflict<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.conflict_type() {
+            FileConflictType::Target => write!(
+                f,
+                "{} exists in both '{}' and '{}'",
+                self.file(),
+                self.target(),
+                self.conflicting_target().unwrap_or("")
+            ),
+            FileConflictType::Filesystem => match self.conflicting_target() {
+                Some(owner) => write!(
+                    f,
+                    "{}: {} exists in filesystem (owned by {})",
+                    self.target(),
+                    self.file(),
+                    owner
+                ),
+                None => write!(f, "{}: {} exists in filesystem", self.target(), self.file()),
+            },
+        }
+    }
+}
+
 impl std::ops::Deref for OwnedFileConflict {
     type Target = FileConflict<'static>;
 
@@ -136,11 +192,21 @@ impl fmt::Debug for OwnedFileConflict {
     }
 }
 
+impl fmt::Display for OwnedFileConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.inner, f)
+    }
+}
+
 impl<'a> FileConflict<'a> {
     pub fn target(&self) -> &'a str {
         unsafe { from_cstr((*self.inner).target) }
     }
 
+    /// The kind of conflict.
+    ///
+    /// [`FileConflictType::Filesystem`] is only reported by libalpm when committing a
+    /// transaction. It is never produced by [`Alpm::check_target_file_conflicts`].
     pub fn conflict_type(&self) -> FileConflictType {
         let t = unsafe { (*self.inner).type_ };
         unsafe { transmute::<alpm_fileconflicttype_t, FileConflictType>(t) }
@@ -168,6 +234,11 @@ impl Drop for OwnedFileConflict {
 }
 
 impl Alpm {
+    /// Checks a set of packages for conflicts between each other.
+    ///
+    /// Only the packages in `list` are compared, the local database is not considered. The
+    /// packages may come from sync databases or be loaded from files. An empty list gives no
+    /// conflicts.
     pub fn check_conflicts<'a, P: 'a + AsPkg + AsAlpmListItemPtr<'a>, L: IntoRawAlpmList<'a, P>>(
         &self,
         list: L,
@@ -176,12 +247,94 @@ impl Alpm {
         let ret = unsafe { alpm_checkconflicts(self.handle, list.list()) };
         AlpmListMut::from_parts(self, ret)
     }
+
+    /// Finds the files that more than one package in `list` would install.
+    ///
+    /// This is not libalpm's file conflict check, which is internal to libalpm and only run
+    /// when committing a transaction. Like [`check_conflicts`](Alpm::check_conflicts) only
+    /// the packages in `list` are compared against each other, so every conflict is of type
+    /// [`FileConflictType::Target`] and conflicts with the filesystem are never reported.
+    /// A path conflicts unless both packages ship it as a directory.
+    ///
+    /// `None` is returned if the file list of a package is not loaded, see
+    /// [`Pkg::has_full_data`].
+    pub fn check_target_file_conflicts<
+        'a,
+        P: 'a + AsPkg + AsAlpmListItemPtr<'a>,
+        L: IntoRawAlpmList<'a, P>,
+    >(
+        &self,
+        list: L,
+    ) -> Option<AlpmListMut<OwnedFileConflict>> {
+        let list = unsafe { list.into_raw_alpm_list() };
+        let mut pkgs = Vec::new();
+        let mut curr = list.list();
+        while !curr.is_null() {
+            unsafe {
+                pkgs.push(Pkg {
+                    handle: self,
+                    pkg: (*curr).data as *mut alpm_pkg_t,
+                });
+                curr = (*curr).next;
+            }
+        }
+
+        if !pkgs.iter().all(|p| p.has_full_data()) {
+            return None;
+        }
+
+        let paths = pkgs
+            .iter()
+            .map(|p| {
+                p.files()
+                    .iter()
+                    .map(|f| split_dir(f.name_bytes()))
+                    .collect::<HashMap<_, _>>()
+            })
+            .collect::<Vec<_>>();
+
+        let root = self.root().as_bytes();
+        let mut ret = ptr::null_mut();
+        for (i, p1) in pkgs.iter().enumerate() {
+            for (p2, p2_paths) in pkgs.iter().zip(&paths).skip(i + 1) {
+                for file in p1.files() {
+                    let (path, is_dir) = split_dir(file.name_bytes());
+                    match p2_paths.get(path) {
+                        Some(&other_is_dir) if !is_dir || !other_is_dir => (),
+                        _ => continue,
+                    }
+
+                    unsafe {
+                        let conflict = dup_struct(alpm_fileconflict_t {
+                            target: dup_str(alpm_pkg_get_name(p1.pkg) as *mut _),
+                            type_: ALPM_FILECONFLICT_TARGET,
+                            file: dup_bytes(&[root, file.name_bytes()].concat()),
+                            ctarget: dup_str(alpm_pkg_get_name(p2.pkg) as *mut _),
+                        });
+                        ret = alpm_list_add(ret, conflict as *mut c_void);
+                    }
+                }
+            }
+        }
+
+        Some(AlpmListMut::from_parts(self, ret))
+    }
+}
+
+// Splits the trailing `/` off a path, returning the path and whether it is a directory.
+fn split_dir(path: &[u8]) -> (&[u8], bool) {
+    match path.strip_suffix(b"/") {
+        Some(path) => (path, true),
+        None => (path, false),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::SigLevel;
+    use crate::{AsDep, Depend, SigLevel};
+    use std::ffi::CString;
+    use std::fs;
 
     #[test]
     fn test_check_conflicts() {
@@ -192,14 +345,188 @@ mod tests {
 
         let i3 = handle.syncdbs().find_satisfier("i3-wm").unwrap();
         let i3gaps = handle.syncdbs().find_satisfier("i3-gaps").unwrap();
-        let conflicts = handle.check_conflicts(vec![i3, i3gaps].iter());
+        let conflicts = handle.check_conflicts([i3, i3gaps].iter());
         let conflict = conflicts.first().unwrap();
         assert_eq!(conflict.package1(), "i3-gaps");
         assert_eq!(conflict.package2(), "i3-wm");
+        assert_eq!(conflict.to_string(), "i3-gaps and i3-wm are in conflict");
 
         let xterm = handle.syncdbs().find_satisfier("xterm").unwrap();
         let systemd = handle.syncdbs().find_satisfier("systemd").unwrap();
-        let conflicts = handle.check_conflicts(vec![xterm, systemd].iter());
+        let conflicts = handle.check_conflicts([xterm, systemd].iter());
+        assert!(conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_check_conflicts_empty() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let pkgs: [crate::Package; 0] = [];
+        assert!(handle.check_conflicts(pkgs.iter()).is_empty());
+    }
+
+    #[test]
+    fn test_check_conflicts_loaded() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let db = handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        let loaded = handle
+            .pkg_load(
+                "tests/pacman-5.1.3-1-x86_64.pkg.tar.xz",
+                false,
+                SigLevel::NONE,
+            )
+            .unwrap();
+        let bash = db.pkg("bash").unwrap();
+
+        let conflicts = handle.check_conflicts([loaded.pkg(), bash.as_pkg()].iter());
+        assert!(conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_check_target_file_conflicts() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let loaded = handle
+            .pkg_load(
+                "tests/pacman-5.1.3-1-x86_64.pkg.tar.xz",
+                true,
+                SigLevel::NONE,
+            )
+            .unwrap();
+        let bash = handle.localdb().pkg("bash").unwrap();
+        let conflicts = handle
+            .check_target_file_conflicts([loaded.pkg(), bash.as_pkg()].iter())
+            .unwrap();
         assert!(conflicts.is_empty());
+
+        let core = handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        let sync_bash = core.pkg("bash").unwrap();
+        assert!(handle
+            .check_target_file_conflicts([sync_bash, bash].iter())
+            .is_none());
+
+        // A local db with vifm, a renamed copy of it that ships the same files and a package
+        // with a directory where vifm has its binary.
+        let tmp = TempDir::new("file-conflicts");
+        let dbpath = tmp.local_db(&[]);
+        let local = dbpath.join("local");
+        let vifm_files = fs::read_to_string("tests/db/local/vifm-0.10-1/files").unwrap();
+        let dir_files = "%FILES%\nusr/\nusr/bin/\nusr/bin/vifm/\nusr/bin/vifm/foo\n\n";
+        for &(name, files) in &[
+            ("vifm", vifm_files.as_str()),
+            ("vifm-fork", vifm_files.as_str()),
+            ("vifm-dir", dir_files),
+        ] {
+            let dir = local.join(format!("{}-0.10-1", name));
+            fs::create_dir_all(&dir).unwrap();
+            let desc = fs::read_to_string("tests/db/local/vifm-0.10-1/desc").unwrap();
+            let desc = desc.replacen("%NAME%\nvifm\n", &format!("%NAME%\n{}\n", name), 1);
+            fs::write(dir.join("desc"), desc).unwrap();
+            fs::write(dir.join("files"), files).unwrap();
+        }
+
+        let handle = Alpm::new("/", dbpath.to_str().unwrap()).unwrap();
+        let vifm = handle.localdb().pkg("vifm").unwrap();
+        let fork = handle.localdb().pkg("vifm-fork").unwrap();
+        let conflicts = handle
+            .check_target_file_conflicts([vifm, fork].iter())
+            .unwrap();
+        assert_eq!(
+            conflicts.len(),
+            vifm.files().iter().filter(|f| !f.is_dir()).count()
+        );
+
+        let conflict = conflicts
+            .iter()
+            .find(|c| c.file() == "/usr/bin/vifm")
+            .unwrap();
+        assert_eq!(conflict.conflict_type(), FileConflictType::Target);
+        assert_eq!(conflict.target(), "vifm");
+        assert_eq!(conflict.conflicting_target(), Some("vifm-fork"));
+        assert_eq!(
+            conflict.to_string(),
+            "/usr/bin/vifm exists in both 'vifm' and 'vifm-fork'"
+        );
+
+        let copy = conflicts.to_list_mut();
+        drop(conflicts);
+        assert!(copy.iter().any(|c| c.file() == "/usr/bin/vifm"));
+        drop(copy);
+
+        let dir = handle.localdb().pkg("vifm-dir").unwrap();
+        let conflicts = handle
+            .check_target_file_conflicts([vifm, dir].iter())
+            .unwrap();
+        let files = conflicts.iter().map(|c| c.file()).collect::<Vec<_>>();
+        assert_eq!(files, ["/usr/bin/vifm"]);
+    }
+
+    #[test]
+    fn test_conflict_display() {
+        let package1 = CString::new("foo").unwrap();
+        let package2 = CString::new("bar").unwrap();
+        let reason = Depend::new("baz<2");
+        let mut raw = alpm_conflict_t {
+            package1_hash: 0,
+            package2_hash: 0,
+            package1: package1.as_ptr() as *mut _,
+            package2: package2.as_ptr() as *mut _,
+            reason: reason.as_dep().inner,
+        };
+        let conflict = Conflict {
+            inner: &mut raw,
+            phantom: PhantomData,
+        };
+        assert_eq!(conflict.to_string(), "foo and bar are in conflict (baz<2)");
+
+        let reason = Depend::new("bar<2");
+        raw.reason = reason.as_dep().inner;
+        let conflict = Conflict {
+            inner: &mut raw,
+            phantom: PhantomData,
+        };
+        assert_eq!(conflict.to_string(), "foo and bar are in conflict");
+    }
+
+    #[test]
+    fn test_file_conflict_display() {
+        let target = CString::new("foo").unwrap();
+        let file = CString::new("/usr/bin/foo").unwrap();
+        let owner = CString::new("bar").unwrap();
+        let empty = CString::new("").unwrap();
+        let mut raw = alpm_fileconflict_t {
+            target: target.as_ptr() as *mut _,
+            type_: ALPM_FILECONFLICT_FILESYSTEM,
+            file: file.as_ptr() as *mut _,
+            ctarget: owner.as_ptr() as *mut _,
+        };
+        let conflict = FileConflict {
+            inner: &mut raw,
+            phantom: PhantomData,
+        };
+        assert_eq!(
+            conflict.to_string(),
+            "foo: /usr/bin/foo exists in filesystem (owned by bar)"
+        );
+
+        raw.ctarget = empty.as_ptr() as *mut _;
+        let conflict = FileConflict {
+            inner: &mut raw,
+            phantom: PhantomData,
+        };
+        assert_eq!(conflict.conflicting_target(), None);
+        assert_eq!(
+            conflict.to_string(),
+            "foo: /usr/bin/foo exists in filesystem"
+        );
+
+        raw.type_ = ALPM_FILECONFLICT_TARGET;
+        raw.ctarget = owner.as_ptr() as *mut _;
+        let conflict = FileConflict {
+            inner: &mut raw,
+            phantom: PhantomData,
+        };
+        assert_eq!(
+            conflict.to_string(),
+            "/usr/bin/foo exists in both 'foo' and 'bar'"
+        );
     }
 }
@@ -3,9 +3,7 @@ use crate::{
     Alpm, AlpmList, AlpmListMut, Group, IntoRawAlpmList, Package, Result, SigLevel, Usage,
 };
 
-use std::ffi::CString;
 use std::fmt;
-use std::ops::Deref;
 
 use alpm_sys::*;
 
@@ -18,7 +16,11 @@ pub struct Db<'a> {
 
 impl<'a> fmt::Debug for Db<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Db").field("name", &self.name()).finish()
+        f.debug_struct("Db")
+            .field("name", &self.name())
+            .field("pkgs", &self.pkgs().len())
+            .field("servers", &DebugTruncated(self.servers().iter()))
+            .finish()
     }
 }
 
@@ -32,14 +34,6 @@ impl<'a> fmt::Debug for DbMut<'a> {
     }
 }
 
-impl<'a> Deref for DbMut<'a> {
-    type Target = Db<'a>;
-
-    fn deref(&self) -> &Db<'a> {
-        &self.inner
-    }
-}
-
 impl<'a> From<DbMut<'a>> for Db<'a> {
     fn from(db: DbMut<'a>) -> Db<'a> {
         db.inner
@@ -48,7 +42,7 @@ impl<'a> From<DbMut<'a>> for Db<'a> {
 
 impl Alpm {
     pub fn register_syncdb<S: Into<Vec<u8>>>(&self, name: S, sig_level: SigLevel) -> Result<Db> {
-        let name = CString::new(name).unwrap();
+        let name = cstring(name)?;
 
         let db =
             unsafe { alpm_register_syncdb(self.handle, name.as_ptr(), sig_level.bits() as i32) };
@@ -57,6 +51,8 @@ impl Alpm {
         Ok(Db { db, handle: self })
     }
 
+    /// Like [`register_syncdb`](Alpm::register_syncdb) but returns a [`DbMut`], so servers
+    /// can be added and the database updated without looking it up again.
     pub fn register_syncdb_mut<S: Into<Vec<u8>>>(
         &mut self,
         name: S,
@@ -66,32 +62,151 @@ impl Alpm {
         Ok(DbMut { inner: db })
     }
 
+    /// Registers a sync database and configures it with `f`.
+    ///
+    /// If `f` fails the database is unregistered again and the error from `f` is returned.
+    pub fn register_syncdb_with<S, F>(
+        &mut self,
+        name: S,
+        sig_level: SigLevel,
+        f: F,
+    ) -> Result<Db<'_>>
+    where
+        S: Into<Vec<u8>>,
+        F: FnOnce(&DbMut) -> Result<()>,
+    {
+        let db = self.register_syncdb_mut(name, sig_level)?;
+
+        if let Err(err) = f(&db) {
+            // The callback's error is the one worth reporting.
+            let _ = db.unregister();
+            return Err(err);
+        }
+
+        Ok(db.into())
+    }
+
+    /// Unregisters every sync database, e.g. before registering them again from a reloaded
+    /// config. Fails with [`Error::TransNotNull`](crate::Error::TransNotNull) while a
+    /// transaction is in progress.
     pub fn unregister_all_syncdbs(&mut self) -> Result<()> {
         self.check_ret(unsafe { alpm_unregister_all_syncdbs(self.handle) })
     }
 }
 
 impl<'a> DbMut<'a> {
-    pub fn unregister(self) {
-        unsafe { alpm_db_unregister(self.db) };
+    /// Unregisters the database from the handle.
+    ///
+    /// Anything borrowed through this `DbMut` must be dropped before the database can be
+    /// unregistered. Fails with [`Error::TransNotNull`](crate::Error::TransNotNull) while a
+    /// transaction is in progress.
+    pub fn unregister(self) -> Result<()> {
+        let ret = unsafe { alpm_db_unregister(self.inner.db) };
+        self.inner.handle.check_ret(ret)
+    }
+
+    // DbMut does not deref to Db: Db is Copy, so a copy taken through deref could outlive
+    // unregister(). Anything borrowed from the db is tied to this DbMut instead.
+
+    /// Borrows the database as a [`Db`] that can not outlive this `DbMut`.
+    pub fn as_db(&self) -> Db<'_> {
+        self.inner
+    }
+
+    pub fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    pub fn servers(&self) -> AlpmList<'_, &str> {
+        self.inner.servers()
+    }
+
+    pub fn pkg<S: Into<Vec<u8>>>(&self, name: S) -> Result<Package<'_>> {
+        self.inner.pkg(name)
+    }
+
+    #[doc(alias = "pkgcache")]
+    pub fn pkgs(&self) -> AlpmList<'_, Package<'_>> {
+        self.inner.pkgs()
+    }
+
+    pub fn group<S: Into<Vec<u8>>>(&self, name: S) -> Result<Group<'_>> {
+        self.inner.group(name)
+    }
+
+    pub fn search<'b, L>(&'b self, list: L) -> Result<AlpmListMut<'b, Package<'b>>>
+    where
+        L: IntoRawAlpmList<'b, String>,
+    {
+        self.inner.search(list)
+    }
+
+    #[doc(alias = "groupcache")]
+    pub fn groups(&self) -> AlpmList<'_, Group<'_>> {
+        self.inner.groups()
+    }
+
+    pub fn set_usage(&self, usage: Usage) -> Result<()> {
+        self.inner.set_usage(usage)
+    }
+
+    pub fn siglevel(&self) -> SigLevel {
+        self.inner.siglevel()
+    }
+
+    #[doc(alias = "db_get_valid")]
+    pub fn is_valid(&self) -> Result<()> {
+        self.inner.is_valid()
+    }
+
+    pub fn usage(&self) -> Result<Usage> {
+        self.inner.usage()
     }
 
     pub fn add_server<S: Into<Vec<u8>>>(&self, server: S) -> Result<()> {
-        let server = CString::new(server).unwrap();
-        let ret = unsafe { alpm_db_add_server(self.db, server.as_ptr()) };
-        self.handle.check_ret(ret)
+        let server = cstring(server)?;
+        let ret = unsafe { alpm_db_add_server(self.inner.db, server.as_ptr()) };
+        self.inner.handle.check_ret(ret)
     }
 
     pub fn set_servers<'b, L: IntoRawAlpmList<'b, String>>(&self, list: L) -> Result<()> {
         let list = unsafe { list.into_raw_alpm_list() };
-        let ret = unsafe { alpm_db_set_servers(self.db, list.list()) };
-        self.handle.check_ret(ret)
+        let ret = unsafe { alpm_db_set_servers(self.inner.db, list.list()) };
+        self.inner.handle.check_ret(ret)
     }
 
     pub fn remove_server<S: Into<Vec<u8>>>(&self, server: S) -> Result<()> {
-        let server = CString::new(server).unwrap();
-        let ret = unsafe { alpm_db_remove_server(self.db, server.as_ptr()) };
-        self.handle.check_ret(ret)
+        let server = cstring(server)?;
+        let ret = unsafe { alpm_db_remove_server(self.inner.db, server.as_ptr()) };
+        self.inner.handle.check_ret(ret)
+    }
+
+    #[cfg(feature = "git")]
+    pub fn cache_servers(&self) -> AlpmList<'_, &str> {
+        self.inner.cache_servers()
+    }
+
+    /// Adds a cache server. Cache servers are tried before the regular servers, and
+    /// failing to download from them is not reported as an error.
+    #[cfg(feature = "git")]
+    pub fn add_cache_server<S: Into<Vec<u8>>>(&self, server: S) -> Result<()> {
+        let server = cstring(server)?;
+        let ret = unsafe { alpm_db_add_cache_server(self.inner.db, server.as_ptr()) };
+        self.inner.handle.check_ret(ret)
+    }
+
+    #[cfg(feature = "git")]
+    pub fn set_cache_servers<'b, L: IntoRawAlpmList<'b, String>>(&self, list: L) -> Result<()> {
+        let list = unsafe { list.into_raw_alpm_list() };
+        let ret = unsafe { alpm_db_set_cache_servers(self.inner.db, list.list()) };
+        self.inner.handle.check_ret(ret)
+    }
+
+    #[cfg(feature = "git")]
+    pub fn remove_cache_server<S: Into<Vec<u8>>>(&self, server: S) -> Result<()> {
+        let server = cstring(server)?;
+        let ret = unsafe { alpm_db_remove_cache_server(self.inner.db, server.as_ptr()) };
+        self.inner.handle.check_ret(ret)
     }
 }
 
@@ -106,13 +221,21 @@ impl<'a> Db<'a> {
         AlpmList::from_parts(self.handle, list)
     }
 
+    /// The cache servers of the database, see [`DbMut::add_cache_server`].
+    #[cfg(feature = "git")]
+    pub fn cache_servers(&self) -> AlpmList<'a, &'a str> {
+        let list = unsafe { alpm_db_get_cache_servers(self.db) };
+        AlpmList::from_parts(self.handle, list)
+    }
+
     pub fn pkg<S: Into<Vec<u8>>>(&self, name: S) -> Result<Package<'a>> {
-        let name = CString::new(name).unwrap();
+        let name = cstring(name)?;
         let pkg = unsafe { alpm_db_get_pkg(self.db, name.as_ptr()) };
         self.handle.check_null(pkg)?;
         unsafe { Ok(Package::new(self.handle, pkg)) }
     }
 
+    /// All packages in the database, sorted by name.
     #[doc(alias = "pkgcache")]
     pub fn pkgs(&self) -> AlpmList<'a, Package<'a>> {
         let pkgs = unsafe { alpm_db_get_pkgcache(self.db) };
@@ -120,7 +243,7 @@ impl<'a> Db<'a> {
     }
 
     pub fn group<S: Into<Vec<u8>>>(&self, name: S) -> Result<Group<'a>> {
-        let name = CString::new(name).unwrap();
+        let name = cstring(name)?;
         let group = unsafe { alpm_db_get_group(self.db, name.as_ptr()) };
         self.handle.check_null(group)?;
         Ok(Group {
@@ -134,6 +257,9 @@ impl<'a> Db<'a> {
         self.handle.check_ret(ret)
     }
 
+    /// Searches the database for packages matching every regex in `list`.
+    ///
+    /// Results are in the same order as [`pkgs`](Db::pkgs).
     pub fn search<L>(&self, list: L) -> Result<AlpmListMut<'a, Package<'a>>>
     where
         L: IntoRawAlpmList<'a, String>,
@@ -145,18 +271,32 @@ impl<'a> Db<'a> {
         Ok(AlpmListMut::from_parts(self.handle, ret))
     }
 
+    /// All groups in the database.
+    ///
+    /// The list is owned by the database. Groups are ordered by the first package, in the
+    /// order of [`pkgs`](Db::pkgs), that is a member of them.
     #[doc(alias = "groupcache")]
-    pub fn groups(&self) -> Result<AlpmListMut<'a, Group<'a>>> {
+    pub fn groups(&self) -> AlpmList<'a, Group<'a>> {
         let groups = unsafe { alpm_db_get_groupcache(self.db) };
-        self.handle.check_null(groups)?;
-        Ok(AlpmListMut::from_parts(self.handle, groups))
+        AlpmList::from_parts(self.handle, groups)
     }
 
+    /// The signature level of the database.
+    ///
+    /// Databases registered with [`SigLevel::USE_DEFAULT`] report the handle's current
+    /// default siglevel. libalpm offers no way to change the level after registration.
     pub fn siglevel(&self) -> SigLevel {
         let siglevel = unsafe { alpm_db_get_siglevel(self.db) };
-        SigLevel::from_bits(siglevel as u32).unwrap()
+        SigLevel::from_raw(siglevel)
     }
 
+    /// Checks the database before it is used.
+    ///
+    /// If the database's siglevel requires a signature it is verified, failing with
+    /// [`Error::DbInvalidSig`](crate::Error::DbInvalidSig) if it is missing or bad. Without a
+    /// required signature only the presence of the file is checked; a database that has not
+    /// been downloaded yet is considered valid. The result is cached by libalpm.
+    #[doc(alias = "db_get_valid")]
     pub fn is_valid(&self) -> Result<()> {
         let ret = unsafe { alpm_db_get_valid(self.db) };
         self.handle.check_ret(ret)
@@ -168,7 +308,7 @@ impl<'a> Db<'a> {
         let ret = unsafe { alpm_db_get_usage(self.db, &mut usage) };
         self.handle.check_ret(ret)?;
 
-        let usage = Usage::from_bits(usage as u32).unwrap();
+        let usage = unsafe { Usage::from_bits_unchecked(usage as u32) };
         Ok(usage)
     }
 }
@@ -176,7 +316,7 @@ impl<'a> Db<'a> {
 #[cfg(test)]
 mod tests {
     use crate::SigLevel;
-    use crate::{Alpm, AlpmListMut};
+    use crate::{Alpm, AlpmListMut, Error, TransFlag, Usage};
 
     #[test]
     fn test_register() {
@@ -186,6 +326,24 @@ mod tests {
         assert_eq!(db.name(), "foo");
     }
 
+    #[test]
+    fn test_siglevel() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let db = handle.register_syncdb("core", SigLevel::PACKAGE).unwrap();
+        assert_eq!(db.siglevel(), SigLevel::PACKAGE);
+
+        let db = handle
+            .register_syncdb("extra", SigLevel::USE_DEFAULT)
+            .unwrap();
+        handle
+            .set_default_siglevel(SigLevel::PACKAGE | SigLevel::DATABASE_OPTIONAL)
+            .unwrap();
+        assert_eq!(
+            db.siglevel(),
+            SigLevel::PACKAGE | SigLevel::DATABASE_OPTIONAL
+        );
+    }
+
     #[test]
     fn test_servers() {
         let mut handle = Alpm::new("/", "tests/db").unwrap();
@@ -211,6 +369,11 @@ mod tests {
         db.set_servers(servers2.iter()).unwrap();
 
         assert_eq!(servers, db.servers().iter().collect::<Vec<_>>());
+
+        let dbs = handle.syncdbs();
+        let db = dbs.first().unwrap();
+        assert_eq!(db.name(), "foo");
+        assert_eq!(servers, db.servers().iter().collect::<Vec<_>>());
     }
 
     #[test]
@@ -225,6 +388,33 @@ mod tests {
         assert_eq!(servers, db.servers().iter().collect::<Vec<_>>());
     }
 
+    #[test]
+    fn test_register_with() {
+        let mut handle = Alpm::new("/", "tests/db").unwrap();
+        let db = handle
+            .register_syncdb_with("core", SigLevel::NONE, |db| {
+                db.add_server("a")?;
+                db.add_server("b")?;
+                db.set_usage(Usage::SYNC | Usage::SEARCH)
+            })
+            .unwrap();
+
+        assert_eq!(db.servers().iter().collect::<Vec<_>>(), ["a", "b"]);
+        assert_eq!(db.usage().unwrap(), Usage::SYNC | Usage::SEARCH);
+
+        let err = handle
+            .register_syncdb_with("extra", SigLevel::NONE, |_| Err(Error::ServerBadUrl))
+            .unwrap_err();
+        assert_eq!(err, Error::ServerBadUrl);
+
+        let names = handle
+            .syncdbs()
+            .iter()
+            .map(|db| db.name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["core"]);
+    }
+
     #[test]
     fn test_mut() {
         let mut handle = Alpm::new("/", "tests/db").unwrap();
@@ -244,12 +434,55 @@ mod tests {
         }
 
         for db in handle.syncdbs_mut() {
-            db.unregister();
+            db.unregister().unwrap();
         }
 
         assert!(handle.syncdbs().is_empty());
     }
 
+    #[test]
+    fn test_unregister() {
+        let mut handle = Alpm::new("/", "tests/db").unwrap();
+        handle.register_syncdb_mut("core", SigLevel::NONE).unwrap();
+        handle.register_syncdb_mut("extra", SigLevel::NONE).unwrap();
+
+        let db = handle.syncdbs_mut().first().unwrap();
+        assert_eq!(db.pkg("linux").unwrap().name(), "linux");
+        assert_eq!(db.as_db().name(), "core");
+        db.unregister().unwrap();
+
+        let names = handle
+            .syncdbs()
+            .iter()
+            .map(|db| db.name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["extra"]);
+
+        handle.unregister_all_syncdbs().unwrap();
+        assert!(handle.syncdbs().is_empty());
+
+        let db = handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        assert_eq!(db.pkg("linux").unwrap().name(), "linux");
+        assert_eq!(handle.syncdbs().len(), 1);
+    }
+
+    #[test]
+    fn test_unregister_in_trans() {
+        let mut handle = Alpm::new("/", "tests/db").unwrap();
+        handle.register_syncdb_mut("core", SigLevel::NONE).unwrap();
+        handle.trans_init(TransFlag::NO_LOCK).unwrap();
+
+        let db = handle.syncdbs_mut().first().unwrap();
+        assert_eq!(db.unregister().unwrap_err(), Error::TransNotNull);
+        assert_eq!(
+            handle.unregister_all_syncdbs().unwrap_err(),
+            Error::TransNotNull
+        );
+
+        handle.trans_release().unwrap();
+        handle.unregister_all_syncdbs().unwrap();
+    }
+
     #[test]
     fn test_pkg() {
         let handle = Alpm::new("/", "tests/db").unwrap();
@@ -258,6 +491,48 @@ mod tests {
         assert!(pkg.version().as_str() == "5.1.8.arch1-1");
     }
 
+    #[test]
+    fn test_pkgs_order() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let db = handle.register_syncdb("core", SigLevel::NONE).unwrap();
+
+        for db in &[db, handle.localdb()] {
+            let names = db.pkgs().iter().map(|p| p.name()).collect::<Vec<_>>();
+            let mut sorted = names.clone();
+            sorted.sort_unstable();
+            assert!(names.len() > 100);
+            assert_eq!(names, sorted);
+        }
+    }
+
+    #[test]
+    fn test_search_order() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let db = handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        let res = db.search(["linux"].iter().cloned()).unwrap();
+        let names = res.iter().map(|p| p.name()).collect::<Vec<_>>();
+
+        let expected = db
+            .pkgs()
+            .iter()
+            .map(|p| p.name())
+            .filter(|name| names.contains(name))
+            .collect::<Vec<_>>();
+        assert!(names.len() > 1);
+        assert_eq!(names, expected);
+    }
+
+    #[test]
+    fn test_groups_order() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let db = handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        let base = db.group("base").unwrap();
+        let names = base.packages().iter().map(|p| p.name()).collect::<Vec<_>>();
+        let mut sorted = names.clone();
+        sorted.sort_unstable();
+        assert_eq!(names, sorted);
+    }
+
     #[test]
     fn test_search() {
         let handle = Alpm::new("/", "tests/db").unwrap();
@@ -291,5 +566,68 @@ mod tests {
         assert_eq!(base.name(), "base");
         assert!(base.packages().len() > 10);
         assert!(base.packages().len() < 100);
+        assert!(base.packages().iter().any(|p| p.name() == "linux"));
+        assert!(base
+            .packages()
+            .iter()
+            .all(|p| p.db().unwrap().name() == "core"));
+    }
+
+    #[test]
+    fn test_groups() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let db = handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        let groups = db.groups();
+        let base = groups.iter().find(|g| g.name() == "base").unwrap();
+        assert_eq!(
+            base.packages().len(),
+            db.group("base").unwrap().packages().len()
+        );
+        assert!(db.groups().iter().any(|g| g.name() == "base-devel"));
+    }
+
+    #[test]
+    #[cfg(feature = "git")]
+    fn test_cache_servers() {
+        let mut handle = Alpm::new("/", "tests/db").unwrap();
+        let db = handle.register_syncdb_mut("core", SigLevel::NONE).unwrap();
+        db.add_server("https://example.com/core").unwrap();
+        db.add_cache_server("http://cache.local/core").unwrap();
+        db.add_cache_server("http://cache2.local/core").unwrap();
+
+        let servers = db.cache_servers().iter().collect::<Vec<_>>();
+        assert_eq!(
+            servers,
+            ["http://cache.local/core", "http://cache2.local/core"]
+        );
+        assert_eq!(
+            db.servers().iter().collect::<Vec<_>>(),
+            ["https://example.com/core"]
+        );
+
+        db.remove_cache_server("http://cache.local/core").unwrap();
+        assert_eq!(db.cache_servers().len(), 1);
+        db.set_cache_servers(["http://a", "http://b"].iter())
+            .unwrap();
+        let db = handle.syncdbs().iter().next().unwrap();
+        assert_eq!(
+            db.cache_servers().iter().collect::<Vec<_>>(),
+            ["http://a", "http://b"]
+        );
+    }
+
+    #[test]
+    fn test_is_valid() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let core = handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        core.is_valid().unwrap();
+
+        let missing = handle
+            .register_syncdb("not-downloaded", SigLevel::NONE)
+            .unwrap();
+        missing.is_valid().unwrap();
+
+        let signed = handle.register_syncdb("extra", SigLevel::DATABASE).unwrap();
+        assert_eq!(signed.is_valid().unwrap_err(), Error::DbInvalidSig);
     }
 }
@@ -1,13 +1,19 @@
 use crate::utils::*;
-use crate::{free, Alpm, AlpmList, AlpmListMut, Db, IntoRawAlpmList, Package, Ver};
+use crate::{
+    free, Alpm, AlpmList, AlpmListMut, Db, Error, IntoRawAlpmList, Package, Pkg, Result, Ver,
+};
 
 use alpm_sys::alpm_depmod_t::*;
 use alpm_sys::*;
 
-use std::ffi::{c_void, CString};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::ffi::c_void;
 use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
 use std::mem::transmute;
+use std::str::FromStr;
 
 pub struct Dep<'a> {
     pub(crate) inner: *mut alpm_depend_t,
@@ -29,18 +35,34 @@ impl<'a> fmt::Debug for Dep<'a> {
 unsafe impl<'a> Send for Dep<'a> {}
 unsafe impl<'a> Sync for Dep<'a> {}
 
-#[derive(PartialEq)]
+/// An owned [`Dep`]. Comparison, hashing and ordering are the same as for [`Dep`].
+#[derive(Eq, Hash, PartialOrd, Ord)]
 pub struct Depend {
     dep: Dep<'static>,
 }
 
 impl Clone for Depend {
     fn clone(&self) -> Self {
-        let ptr = unsafe { alpm_dep_compute_string(self.inner) };
-        assert!(!ptr.is_null(), "failed to compute string for dep");
-        let dep = unsafe { alpm_dep_from_string(ptr) };
-        assert!(!dep.is_null(), "failed to create dep from string");
-        unsafe { Depend::from_ptr(dep) }
+        unsafe { Depend::from_ptr(dep_dup(self.inner)) }
+    }
+}
+
+/// Deep copies a dependency. libalpm does not export `alpm_dep_dup`.
+pub(crate) unsafe fn dep_dup(dep: *mut alpm_depend_t) -> *mut alpm_depend_t {
+    if dep.is_null() {
+        return dep;
+    }
+    let ptr = alpm_dep_compute_string(dep);
+    assert!(!ptr.is_null(), "failed to compute string for dep");
+    let dup = alpm_dep_from_string(ptr);
+    free(ptr as *mut c_void);
+    assert!(!dup.is_null(), "failed to create dep from string");
+    dup
+}
+
+impl<D: AsDep> PartialEq<D> for Depend {
+    fn eq(&self, other: &D) -> bool {
+        self.dep == other.as_dep()
     }
 }
 
@@ -56,6 +78,15 @@ impl fmt::Display for Depend {
     }
 }
 
+/// Parses a dependency string. See [`Depend::try_new`].
+impl FromStr for Depend {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Depend> {
+        Depend::try_new(s)
+    }
+}
+
 impl std::ops::Deref for Depend {
     type Target = Dep<'static>;
 
@@ -74,6 +105,12 @@ impl<'a> AsDep for Depend {
     }
 }
 
+impl AsDep for &Depend {
+    fn as_dep(&self) -> Dep<'_> {
+        self.dep()
+    }
+}
+
 impl<'a> AsDep for Dep<'a> {
     fn as_dep(&self) -> Dep {
         self.dep()
@@ -92,8 +129,10 @@ impl Drop for Depend {
     }
 }
 
-impl<'a> PartialEq for Dep<'a> {
-    fn eq(&self, other: &Self) -> bool {
+/// Dependencies are compared by name, depmod, version and description.
+impl<'a, D: AsDep> PartialEq<D> for Dep<'a> {
+    fn eq(&self, other: &D) -> bool {
+        let other = other.as_dep();
         self.name() == other.name()
             && self.depmod() == other.depmod()
             && self.version() == other.version()
@@ -101,6 +140,39 @@ impl<'a> PartialEq for Dep<'a> {
     }
 }
 
+impl<'a> Eq for Dep<'a> {}
+
+impl<'a> Hash for Dep<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // versions are compared with vercmp so "1" and "1-1" are equal and can't be hashed
+        self.name().hash(state);
+        self.depmod().hash(state);
+        self.desc().hash(state);
+    }
+}
+
+impl<'a> PartialOrd for Dep<'a> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Dependencies are sorted by name then version.
+impl<'a> Ord for Dep<'a> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let version = match (self.version(), other.version()) {
+            (Some(a), Some(b)) => a.partial_cmp(b).unwrap(),
+            (a, b) => a.is_some().cmp(&b.is_some()),
+        };
+
+        self.name()
+            .cmp(other.name())
+            .then(version)
+            .then(self.depmod().cmp(&other.depmod()))
+            .then(self.desc().cmp(&other.desc()))
+    }
+}
+
 impl<'a> fmt::Display for Dep<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         unsafe {
@@ -128,17 +200,52 @@ impl<'a> From<Dep<'a>> for Vec<u8> {
 }
 
 impl Depend {
+    /// Parses a dependency string such as `foo>=1.2-3: for bar`.
+    ///
+    /// Panics if the string contains a nul byte, use [`try_new`](Depend::try_new) for
+    /// strings that come from the user.
     pub fn new<S: Into<Vec<u8>>>(s: S) -> Depend {
-        let s = CString::new(s).unwrap();
+        Depend::try_new(s).unwrap()
+    }
+
+    /// Parses a dependency string such as `foo>=1.2-3: for bar`.
+    ///
+    /// Fails with [`Error::WrongArgs`] if the string contains a nul byte.
+    pub fn try_new<S: Into<Vec<u8>>>(s: S) -> Result<Depend> {
+        let s = cstring(s)?;
         let dep = unsafe { alpm_dep_from_string(s.as_ptr()) };
         assert!(!dep.is_null(), "failed to create dep from string");
 
-        Depend {
+        Ok(Depend {
             dep: Dep {
                 inner: dep,
                 phantom: PhantomData,
             },
+        })
+    }
+
+    /// Builds a dependency from its parts.
+    ///
+    /// This is the inverse of [`name`](Dep::name), [`depmodver`](Dep::depmodver) and
+    /// [`desc`](Dep::desc). Fails with [`Error::WrongArgs`] if a part contains a nul byte.
+    pub fn from_parts(name: &str, depmodver: DepModVer, desc: Option<&str>) -> Result<Depend> {
+        let mut s = name.to_string();
+
+        match depmodver {
+            DepModVer::Any => (),
+            DepModVer::Eq(v) => s.extend(["=", v.as_str()].iter().copied()),
+            DepModVer::Ge(v) => s.extend([">=", v.as_str()].iter().copied()),
+            DepModVer::Le(v) => s.extend(["<=", v.as_str()].iter().copied()),
+            DepModVer::Gt(v) => s.extend([">", v.as_str()].iter().copied()),
+            DepModVer::Lt(v) => s.extend(["<", v.as_str()].iter().copied()),
+        }
+
+        if let Some(desc) = desc {
+            s.push_str(": ");
+            s.push_str(desc);
         }
+
+        Depend::try_new(s)
     }
 
     pub(crate) unsafe fn from_ptr(ptr: *mut alpm_depend_t) -> Depend {
@@ -163,6 +270,12 @@ impl<'a> Dep<'a> {
         Depend::new(self.to_string())
     }
 
+    /// Checks if `pkg` satisfies this dependency. See [`Pkg::satisfies`].
+    #[doc(alias = "satisfies")]
+    pub fn satisfied_by(&self, pkg: &Pkg) -> bool {
+        pkg.satisfies(self)
+    }
+
     pub fn name(&self) -> &'a str {
         unsafe { from_cstr((*self.inner).name) }
     }
@@ -283,6 +396,32 @@ impl fmt::Debug for DependMissing {
     }
 }
 
+impl fmt::Display for DependMissing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.inner, f)
+    }
+}
+
+impl<'a> fmt::Display for DepMissing<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.causing_pkg() {
+            Some(causing_pkg) => write!(
+                f,
+                "installing {} breaks dependency '{}' required by {}",
+                causing_pkg,
+                self.depend(),
+                self.target()
+            ),
+            None => write!(
+                f,
+                "unable to satisfy dependency '{}' required by {}",
+                self.depend(),
+                self.target()
+            ),
+        }
+    }
+}
+
 impl Drop for DependMissing {
     fn drop(&mut self) {
         unsafe { alpm_depmissing_free(self.inner.inner) }
@@ -312,8 +451,14 @@ impl<'a> DepMissing<'a> {
 }
 
 impl<'a> AlpmList<'a, Db<'a>> {
+    /// Finds a package satisfying `dep`, by name or provide, in the databases.
+    ///
+    /// The databases are searched in order so the first database with a satisfier wins,
+    /// which for [`syncdbs`](Alpm::syncdbs) is the order they were registered in. `None`
+    /// is returned if nothing satisfies `dep`.
+    #[doc(alias = "find_dbs_satisfier")]
     pub fn find_satisfier<S: Into<Vec<u8>>>(&self, dep: S) -> Option<Package<'a>> {
-        let dep = CString::new(dep).unwrap();
+        let dep = cstring(dep).ok()?;
 
         let pkg = unsafe { alpm_find_dbs_satisfier(self.handle.handle, self.list, dep.as_ptr()) };
         self.handle.check_null(pkg).ok()?;
@@ -321,17 +466,185 @@ impl<'a> AlpmList<'a, Db<'a>> {
     }
 }
 
+impl Alpm {
+    /// Finds every package in `dbs` satisfying `dep`, by name or provide.
+    ///
+    /// Unlike [`find_satisfier`](AlpmList::find_satisfier) this returns all candidates so
+    /// the user can be asked to pick a provider. Version constraints are checked against
+    /// the package version or the version of the provide. Packages are returned in database
+    /// order.
+    ///
+    /// Fails with [`Error::WrongArgs`] if `dep` contains a nul byte.
+    pub fn providers<'a, T: IntoRawAlpmList<'a, Db<'a>>>(
+        &'a self,
+        dbs: T,
+        dep: &str,
+    ) -> Result<AlpmListMut<'a, Package<'a>>> {
+        let dep = Depend::try_new(dep)?;
+        let dbs = unsafe { dbs.into_raw_alpm_list() };
+        let dbs = AlpmList::<Db>::from_parts(self, dbs.list());
+        let mut ret = AlpmListMut::new(self);
+
+        for db in dbs {
+            for pkg in db.pkgs() {
+                let could_satisfy = pkg.name() == dep.name()
+                    || pkg.provides().iter().any(|p| p.name() == dep.name());
+                if could_satisfy && pkg.satisfies(&dep) {
+                    ret.push(pkg);
+                }
+            }
+        }
+
+        Ok(ret)
+    }
+}
+
 impl<'a> AlpmList<'a, Package<'a>> {
+    /// Finds a package satisfying `dep`, by name or provide, in the list.
+    ///
+    /// `None` is returned if nothing satisfies `dep`.
     pub fn find_satisfier<S: Into<Vec<u8>>>(&self, dep: S) -> Option<Package<'a>> {
-        let dep = CString::new(dep).unwrap();
+        let dep = cstring(dep).ok()?;
 
         let pkg = unsafe { alpm_find_satisfier(self.list, dep.as_ptr()) };
         self.handle.check_null(pkg).ok()?;
         unsafe { Some(Package::new(self.handle, pkg)) }
     }
+
+    /// Finds the package called `name` in the list. Provides are not considered.
+    ///
+    /// This compares name hashes so it is cheaper than iterating, but still walks the list.
+    /// Use [`Db::pkg`] to look up a package in a database.
+    #[doc(alias = "pkg_find")]
+    pub fn find_pkg<S: Into<Vec<u8>>>(&self, name: S) -> Option<Package<'a>> {
+        let name = cstring(name).ok()?;
+
+        let pkg = unsafe { alpm_pkg_find(self.list, name.as_ptr()) };
+        if pkg.is_null() {
+            None
+        } else {
+            unsafe { Some(Package::new(self.handle, pkg)) }
+        }
+    }
+
+    /// Whether the list has a package called `name`.
+    pub fn contains_pkg<S: Into<Vec<u8>>>(&self, name: S) -> bool {
+        self.find_pkg(name).is_some()
+    }
+}
+
+#[derive(Debug)]
+enum SatisfierSource<'a> {
+    Db(Db<'a>),
+    Dbs(AlpmList<'a, Db<'a>>),
+}
+
+/// Memoizes `find_satisfier` lookups against a database or a list of databases.
+///
+/// Results are cached by dep string. The cache is not aware of changes to the underlying
+/// databases so entries must be invalidated manually when they change.
+///
+/// The cache can be passed to [`Pkg::required_by_in`] and [`Pkg::reverse_deps`], which
+/// check the same dep strings against the same package over and over.
+#[derive(Debug)]
+pub struct SatisfierCache<'a> {
+    source: SatisfierSource<'a>,
+    cache: HashMap<String, Option<Package<'a>>>,
+    satisfies: HashMap<(String, *mut alpm_pkg_t), bool>,
+    hits: usize,
+    misses: usize,
+}
+
+impl<'a> SatisfierCache<'a> {
+    pub fn new(dbs: AlpmList<'a, Db<'a>>) -> SatisfierCache<'a> {
+        SatisfierCache::with_source(SatisfierSource::Dbs(dbs))
+    }
+
+    pub fn from_db(db: Db<'a>) -> SatisfierCache<'a> {
+        SatisfierCache::with_source(SatisfierSource::Db(db))
+    }
+
+    fn with_source(source: SatisfierSource<'a>) -> SatisfierCache<'a> {
+        SatisfierCache {
+            source,
+            cache: HashMap::new(),
+            satisfies: HashMap::new(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    pub fn find_satisfier<S: AsRef<str>>(&mut self, dep: S) -> Option<Package<'a>> {
+        let dep = dep.as_ref();
+
+        if let Some(pkg) = self.cache.get(dep) {
+            self.hits += 1;
+            return *pkg;
+        }
+
+        self.misses += 1;
+        let pkg = match self.source {
+            SatisfierSource::Db(db) => db.pkgs().find_satisfier(dep),
+            SatisfierSource::Dbs(dbs) => dbs.find_satisfier(dep),
+        };
+        self.cache.insert(dep.to_string(), pkg);
+        pkg
+    }
+
+    /// Checks if `pkg` satisfies `dep`, see [`Pkg::satisfies`].
+    ///
+    /// Results are cached by dep string and package, whatever databases the cache was
+    /// created with.
+    pub fn satisfies<D: AsDep>(&mut self, pkg: &Pkg, dep: D) -> bool {
+        let key = (dep.as_dep().to_string(), pkg.pkg);
+
+        if let Some(&satisfies) = self.satisfies.get(&key) {
+            self.hits += 1;
+            return satisfies;
+        }
+
+        self.misses += 1;
+        let satisfies = pkg.satisfies(dep);
+        self.satisfies.insert(key, satisfies);
+        satisfies
+    }
+
+    pub fn invalidate<S: AsRef<str>>(&mut self, dep: S) -> bool {
+        let dep = dep.as_ref();
+        let len = self.satisfies.len();
+        self.satisfies.retain(|(d, _), _| d != dep);
+        let removed = self.cache.remove(dep).is_some();
+        removed || self.satisfies.len() != len
+    }
+
+    pub fn clear(&mut self) {
+        self.cache.clear();
+        self.satisfies.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.cache.len() + self.satisfies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
 }
 
 impl Alpm {
+    /// Checks for dependencies that would break without starting a transaction.
+    ///
+    /// `pkgs` is the set of installed packages, `rem` the packages to remove and `upgrade`
+    /// the packages to install or upgrade. With `reverse_deps` packages in `pkgs` that
+    /// depend on something being removed or upgraded are checked too.
     pub fn check_deps<'a>(
         &self,
         pkgs: impl IntoRawAlpmList<'a, Package<'a>>,
@@ -361,7 +674,76 @@ impl Alpm {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::SigLevel;
+    use crate::{SigLevel, Version};
+    use std::collections::{BTreeSet, HashSet};
+
+    #[test]
+    fn test_pkg_list_find() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let db = handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        let pkgs = db.pkgs();
+
+        let linux = pkgs.find_pkg("linux").unwrap();
+        assert_eq!(linux.name(), "linux");
+        assert_eq!(linux.version().as_str(), "5.1.8.arch1-1");
+        assert!(pkgs.find_pkg("not-a-package").is_none());
+        assert!(pkgs.find_pkg("sh").is_none());
+        assert!(pkgs.find_pkg("lin\0ux").is_none());
+        assert!(pkgs.contains_pkg("bash"));
+        assert!(!pkgs.contains_pkg("not-a-package"));
+
+        let mut list = AlpmListMut::new(&handle);
+        list.push(linux);
+        assert!(list.find_pkg("linux").is_some());
+        assert!(list.find_pkg("bash").is_none());
+        assert!(AlpmListMut::<Package>::new(&handle)
+            .find_pkg("linux")
+            .is_none());
+    }
+
+    #[test]
+    fn test_providers() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        handle.register_syncdb("extra", SigLevel::NONE).unwrap();
+        handle.register_syncdb("community", SigLevel::NONE).unwrap();
+
+        let names = |dep| {
+            handle
+                .providers(handle.syncdbs(), dep)
+                .unwrap()
+                .iter()
+                .map(|p| p.name().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let java = names("java-runtime-openjdk");
+        assert_eq!(java.len(), 4);
+        let mut java = names("java-runtime-openjdk>=10");
+        java.sort();
+        assert_eq!(java, ["jre-openjdk", "jre10-openjdk"]);
+        assert_eq!(names("java-runtime-openjdk=8"), ["jre8-openjdk"]);
+        assert!(names("java-runtime-openjdk>11").is_empty());
+
+        let smtp = handle
+            .providers(handle.syncdbs(), "smtp-forwarder")
+            .unwrap();
+        let dbs = smtp
+            .iter()
+            .map(|p| p.db().unwrap().name())
+            .collect::<Vec<_>>();
+        assert_eq!(dbs.len(), 6);
+        assert_eq!(dbs[..2], ["extra", "extra"]);
+        assert!(dbs[2..].iter().all(|db| *db == "community"));
+
+        assert_eq!(names("bash"), ["bash"]);
+        assert_eq!(names("sh"), ["bash"]);
+        assert!(names("not-a-package").is_empty());
+        assert_eq!(
+            handle.providers(handle.syncdbs(), "sh\0").unwrap_err(),
+            Error::WrongArgs
+        );
+    }
 
     #[test]
     fn test_depend() {
@@ -371,17 +753,143 @@ mod tests {
         let dep = Depend::new("<3");
         assert_eq!(dep.name(), "");
         assert_eq!(dep.version().unwrap().as_str(), "3");
+
+        assert_eq!(Depend::try_new("abc").unwrap(), Depend::new("abc"));
+        assert_eq!("abc>=1".parse::<Depend>().unwrap(), Depend::new("abc>=1"));
+        assert_eq!(Depend::try_new("abc\0").unwrap_err(), Error::WrongArgs);
+        assert_eq!("abc\0".parse::<Depend>().unwrap_err(), Error::WrongArgs);
     }
 
     #[test]
-    fn test_depend_lifetime() {
+    fn test_depend_display() {
+        assert_eq!(Depend::new("foo").to_string(), "foo");
+        assert_eq!(Depend::new("foo>=1.2-3").to_string(), "foo>=1.2-3");
+        assert_eq!(
+            Depend::new("foo>=1.2-3: for bar").to_string(),
+            "foo>=1.2-3: for bar"
+        );
+
+        let dep = Depend::new("glibc>=2.30");
+        assert_eq!(dep.name(), "glibc");
+        assert_eq!(dep.depmod(), DepMod::Ge);
+        assert_eq!(dep.version().unwrap().as_str(), "2.30");
+        assert_eq!(dep.to_string(), "glibc>=2.30");
+    }
+
+    #[test]
+    fn test_depend_from_parts() {
+        let ver = Version::new("1:1.2-3");
+        let dep = Depend::from_parts("foo", DepModVer::Ge(&ver), None).unwrap();
+        assert_eq!(dep.to_string(), "foo>=1:1.2-3");
+        assert_eq!(dep.depmod(), DepMod::Ge);
+        assert_eq!(dep.version().unwrap(), &*ver);
+        assert_eq!(dep, Depend::new("foo>=1:1.2-3"));
+
+        let dep = Depend::from_parts("foo", DepModVer::Any, Some("for bar")).unwrap();
+        assert_eq!(dep.to_string(), "foo: for bar");
+        assert_eq!(
+            Depend::from_parts("foo\0", DepModVer::Any, None).unwrap_err(),
+            Error::WrongArgs
+        );
+
+        for s in &[
+            "foo",
+            "foo<1",
+            "foo<=1",
+            "foo=1-1",
+            "foo>1",
+            "foo=1: for bar",
+        ] {
+            let dep = Depend::new(*s);
+            let parts = Depend::from_parts(dep.name(), dep.depmodver(), dep.desc()).unwrap();
+            assert_eq!(dep, parts);
+            assert_eq!(parts.to_string(), *s);
+        }
+    }
+
+    #[test]
+    fn test_dep_accessors() {
         let handle = Alpm::new("/", "tests/db").unwrap();
         let db = handle.register_syncdb("core", SigLevel::NONE).unwrap();
+
         let pkg = db.pkg("linux").unwrap();
-        let depends = pkg.depends();
-        let vec = depends.iter().collect::<Vec<_>>();
-        drop(pkg);
-        drop(db);
+        let kmod = pkg.depends().iter().find(|d| d.name() == "kmod").unwrap();
+        assert_eq!(kmod.depmod(), DepMod::Any);
+        assert!(kmod.version().is_none());
+        assert!(kmod.desc().is_none());
+
+        let crda = pkg.optdepends().first().unwrap();
+        assert_eq!(crda.name(), "crda");
+        assert_eq!(
+            crda.desc(),
+            Some("to set the correct wireless channels of your country")
+        );
+
+        let pkg = db.pkg("linux-lts").unwrap();
+        let mkinitcpio = pkg.depends().iter().last().unwrap();
+        assert_eq!(mkinitcpio.name(), "mkinitcpio");
+        assert_eq!(mkinitcpio.depmod(), DepMod::Ge);
+        assert_eq!(mkinitcpio.version().unwrap().as_str(), "0.7");
+        assert!(mkinitcpio.desc().is_none());
+    }
+
+    #[test]
+    fn test_satisfied_by_provides() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let bash = handle.localdb().pkg("bash").unwrap();
+        let audit = handle.localdb().pkg("audit").unwrap();
+
+        assert!(Depend::new("sh").satisfied_by(&bash));
+        assert!(!Depend::new("sh>=1").satisfied_by(&bash));
+        assert!(Depend::new("libaudit.so=1-64").satisfied_by(&audit));
+        assert!(!Depend::new("libaudit.so>1").satisfied_by(&audit));
+        assert!(!Depend::new("libauparse.so=1-64").satisfied_by(&audit));
+
+        let sh = bash.provides().first().unwrap();
+        assert!(sh.satisfied_by(&bash));
+        assert!(!sh.satisfied_by(&audit));
+    }
+
+    #[test]
+    fn test_depend_eq_hash_ord() {
+        let set = ["foo>=1", "bar", "foo>=1", "bar: desc", "bar"]
+            .iter()
+            .map(|s| Depend::new(*s))
+            .collect::<HashSet<_>>();
+        assert_eq!(set.len(), 3);
+
+        let set = set.into_iter().collect::<BTreeSet<_>>();
+        let set = set.iter().map(|d| d.to_string()).collect::<Vec<_>>();
+        assert_eq!(set, ["bar", "bar: desc", "foo>=1"]);
+
+        let mut deps = ["foo<2", "foo", "foo=1.10", "foo=1.9", "abc=2"]
+            .iter()
+            .map(|s| Depend::new(*s))
+            .collect::<Vec<_>>();
+        deps.sort();
+        let deps = deps.iter().map(|d| d.to_string()).collect::<Vec<_>>();
+        assert_eq!(deps, ["abc=2", "foo", "foo=1.9", "foo=1.10", "foo<2"]);
+
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let bash = handle.localdb().pkg("bash").unwrap();
+        let sh = bash.provides().first().unwrap();
+        assert_eq!(sh, Depend::new("sh"));
+        assert_eq!(Depend::new("sh"), sh);
+        assert_ne!(sh, Depend::new("sh=1"));
+
+        let depend = Depend::new("foo=1: desc");
+        assert_eq!(depend.clone(), depend);
+    }
+
+    #[test]
+    fn test_depend_lifetime() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let vec = {
+            let db = handle.register_syncdb("core", SigLevel::NONE).unwrap();
+            let pkg = db.pkg("linux").unwrap();
+            let depends = pkg.depends();
+            depends.iter().collect::<Vec<_>>()
+        };
         println!("{:?}", vec);
     }
 
@@ -390,6 +898,21 @@ mod tests {
         assert_eq!(Depend::new("foo=1"), Depend::new("foo=1"));
         assert_ne!(Depend::new("foo=1-1"), Depend::new("foo=1-2"));
         assert_ne!(Depend::new("foo=2"), Depend::new("foo=1"));
+        assert_eq!(Depend::new("foo"), Depend::new("foo"));
+        assert_ne!(Depend::new("foo>=1"), Depend::new("foo>=2"));
+        assert_ne!(Depend::new("foo>=1"), Depend::new("foo<=1"));
+    }
+
+    #[test]
+    fn test_depend_sort_dedup() {
+        let mut deps = ["foo>=2", "bar", "foo>=1", "foo", "bar", "foo>=2"]
+            .iter()
+            .map(|s| Depend::new(*s))
+            .collect::<Vec<_>>();
+        deps.sort();
+        deps.dedup();
+        let deps = deps.iter().map(|d| d.to_string()).collect::<Vec<_>>();
+        assert_eq!(deps, ["bar", "foo", "foo>=1", "foo>=2"]);
     }
 
     #[test]
@@ -398,17 +921,51 @@ mod tests {
         handle.register_syncdb("extra", SigLevel::NONE).unwrap();
         handle.register_syncdb("community", SigLevel::NONE).unwrap();
 
-        let pkgs1 = handle.localdb().pkgs();
-        let pkgs = pkgs1.iter().collect::<Vec<_>>();
-        drop(pkgs1);
+        let pkgs = handle.localdb().pkgs().iter().collect::<Vec<_>>();
         let rem = handle.localdb().pkg("ncurses").unwrap();
+        let missing =
+            handle.check_deps(pkgs.iter(), [rem].iter(), &AlpmListMut::new(&handle), true);
+        assert_eq!(missing.len(), 9);
+    }
+
+    #[test]
+    fn test_check_deps_remove() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let db = handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        let kmod = db.pkg("kmod").unwrap();
+
+        let missing = handle.check_deps(db.pkgs(), [kmod].iter(), &AlpmListMut::new(&handle), true);
+        let linux = missing.iter().find(|m| m.target() == "linux").unwrap();
+        assert_eq!(linux.depend().name(), "kmod");
+        assert!(missing.iter().all(|m| m.target() != "kmod"));
+
+        let missing =
+            handle.check_deps(db.pkgs(), [kmod].iter(), &AlpmListMut::new(&handle), false);
+        assert!(missing.is_empty());
+    }
+
+    #[test]
+    fn test_dep_missing_display() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+
+        let pkgs1 = handle.localdb().pkgs();
+        let pkgs = pkgs1
+            .iter()
+            .filter(|p| p.name() != "ncurses")
+            .collect::<Vec<_>>();
+        let vifm = handle.localdb().pkg("vifm").unwrap();
         let missing = handle.check_deps(
             pkgs.iter(),
-            vec![rem].iter(),
             &AlpmListMut::new(&handle),
-            true,
+            [vifm].iter(),
+            false,
+        );
+
+        assert_eq!(missing.len(), 1);
+        assert_eq!(
+            missing.first().unwrap().to_string(),
+            "unable to satisfy dependency 'ncurses' required by vifm"
         );
-        assert_eq!(missing.len(), 9);
     }
 
     #[test]
@@ -424,4 +981,78 @@ mod tests {
         let pkg = handle.syncdbs().find_satisfier("linux>0").unwrap();
         assert_eq!(pkg.name(), "linux");
     }
+
+    #[test]
+    fn test_find_satisfier_provides() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        let extra = handle.register_syncdb("extra", SigLevel::NONE).unwrap();
+
+        let flatpak = extra.pkg("flatpak").unwrap();
+        let ostree = flatpak.depends().iter().find(|d| d.name() == "ostree");
+        let ostree = ostree.unwrap().to_string();
+        let pkgs = extra.pkgs();
+        assert_eq!(pkgs.find_satisfier(ostree).unwrap().name(), "ostree");
+        assert_eq!(
+            pkgs.find_satisfier("ostree>=2019").unwrap().name(),
+            "ostree"
+        );
+        assert!(pkgs.find_satisfier("ostree>2019.2").is_none());
+
+        let pkg = pkgs.find_satisfier("libavcodec.so=58-64").unwrap();
+        assert_eq!(pkg.name(), "ffmpeg");
+        assert!(pkgs.find_satisfier("libavcodec.so=59-64").is_none());
+
+        let pkg = handle.syncdbs().find_satisfier("libavcodec.so>=58-64");
+        assert_eq!(pkg.unwrap().name(), "ffmpeg");
+        assert!(handle.syncdbs().find_satisfier("does-not-exist").is_none());
+    }
+
+    #[test]
+    fn test_satisfier_cache() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        handle.register_syncdb("extra", SigLevel::NONE).unwrap();
+
+        let deps = ["glibc", "gcc-libs", "linux>0", "sh", "doesnotexist"];
+        let mut cache = SatisfierCache::new(handle.syncdbs());
+
+        for _ in 0..2 {
+            for dep in &deps {
+                let cached = cache.find_satisfier(dep).map(|p| p.pkg.pkg);
+                let uncached = handle.syncdbs().find_satisfier(*dep).map(|p| p.pkg.pkg);
+                assert_eq!(cached, uncached);
+            }
+        }
+
+        assert_eq!(cache.len(), deps.len());
+        assert_eq!(cache.misses(), deps.len());
+        assert_eq!(cache.hits(), deps.len());
+
+        let mut cache = SatisfierCache::from_db(handle.localdb());
+        assert_eq!(cache.find_satisfier("vifm").unwrap().name(), "vifm");
+        assert_eq!(cache.hits(), 0);
+        assert_eq!(cache.misses(), 1);
+    }
+
+    #[test]
+    fn test_satisfier_cache_invalidate() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        handle.register_syncdb("core", SigLevel::NONE).unwrap();
+
+        let mut cache = SatisfierCache::new(handle.syncdbs());
+        assert!(cache.find_satisfier("i3-wm").is_none());
+
+        handle.register_syncdb("community", SigLevel::NONE).unwrap();
+        assert!(cache.find_satisfier("i3-wm").is_none());
+
+        assert!(cache.invalidate("i3-wm"));
+        assert!(!cache.invalidate("i3-wm"));
+        assert_eq!(cache.find_satisfier("i3-wm").unwrap().name(), "i3-wm");
+
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(cache.hits(), 1);
+        assert_eq!(cache.misses(), 2);
+    }
 }
@@ -1,14 +1,26 @@
-use crate::{Alpm, AlpmListMut, IntoRawAlpmList, Result};
+use crate::{Alpm, AlpmListMut, Error, IntoRawAlpmList, Result};
 
 use alpm_sys::*;
 
 use std::ptr;
 
 impl Alpm {
+    /// Downloads the given package URLs into the cache and returns the paths of the
+    /// downloaded files.
+    ///
+    /// Files are downloaded into the first writable cachedir. If no cachedir is usable
+    /// libalpm falls back to `$TMPDIR` or `/tmp`. Downloads honor
+    /// [`set_parallel_downloads`](Alpm::set_parallel_downloads), the fetch callback and the
+    /// download callback. Signatures are fetched alongside the packages when the default
+    /// siglevel asks for them.
+    ///
+    /// Fails with [`Error::Retrieve`](crate::Error::Retrieve) if any file could not be
+    /// downloaded. libalpm does not say which, the download callback receives a
+    /// completed event with the result of each file.
     pub fn fetch_pkgurl<'a, L: IntoRawAlpmList<'a, String>>(
-        &'a self,
+        &self,
         urls: L,
-    ) -> Result<AlpmListMut<'a, String>> {
+    ) -> Result<AlpmListMut<'_, String>> {
         let mut out = ptr::null_mut();
         let list = unsafe { urls.into_raw_alpm_list() };
         let ret = unsafe { alpm_fetch_pkgurl(self.handle, list.list(), &mut out) };
@@ -16,4 +28,136 @@ impl Alpm {
         let fetched = AlpmListMut::from_parts(self, out);
         Ok(fetched)
     }
+
+    /// Like [`fetch_pkgurl`](Alpm::fetch_pkgurl) but downloads each url separately, trying
+    /// up to `retries` more times when a download fails.
+    ///
+    /// Only [`Error::Retrieve`] is retried, other errors are not caused by the network.
+    /// libalpm has no retry option of its own so every attempt is a new download.
+    pub fn fetch_pkgurl_with_retries<I, S>(&self, urls: I, retries: u32) -> FetchSummary
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut summary = FetchSummary::default();
+
+        for url in urls {
+            let url = url.as_ref();
+            let mut attempts = 0;
+
+            loop {
+                attempts += 1;
+                match self.fetch_pkgurl(std::iter::once(url)) {
+                    Ok(fetched) => {
+                        summary
+                            .fetched
+                            .extend(fetched.iter().map(|path| path.to_string()));
+                        break;
+                    }
+                    Err(Error::Retrieve) if attempts <= retries => continue,
+                    Err(err) => {
+                        summary.failed.push((url.to_string(), err));
+                        break;
+                    }
+                }
+            }
+        }
+
+        summary
+    }
+}
+
+/// The result of [`Alpm::fetch_pkgurl_with_retries`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FetchSummary {
+    /// The paths of the downloaded files.
+    pub fetched: Vec<String>,
+    /// The urls that could not be downloaded, with the error of the last attempt.
+    pub failed: Vec<(String, Error)>,
+}
+
+impl FetchSummary {
+    /// Whether every url was downloaded.
+    pub fn is_ok(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::TempDir;
+    use crate::{Alpm, Error, FetchResult, SigLevel};
+    use std::cell::RefCell;
+    use std::fs;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_fetch_pkgurl_error() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        assert!(handle.cachedirs().is_empty());
+
+        let urls = ["file:///does/not/exist/foo-1-1-any.pkg.tar.zst"];
+        assert!(handle.fetch_pkgurl(urls.iter()).is_err());
+    }
+
+    #[test]
+    fn test_fetch_pkgurl_cb() {
+        let cachedir = TempDir::new("fetch-pkgurl");
+
+        let mut handle = Alpm::new("/", "tests/db").unwrap();
+        handle.add_cachedir(cachedir.to_str().unwrap()).unwrap();
+        handle.set_default_siglevel(SigLevel::NONE).unwrap();
+        handle.set_fetch_cb(Vec::new(), |url, path, _force, urls: &mut Vec<String>| {
+            urls.push(url.to_string());
+            let name = url.rsplit('/').next().unwrap();
+            fs::write(format!("{}/{}", path, name), "pkg").unwrap();
+            FetchResult::Ok
+        });
+
+        let urls = ["http://example.invalid/foo-1-1-any.pkg.tar.zst"];
+        let fetched = handle.fetch_pkgurl(urls.iter()).unwrap();
+        let fetched = fetched.iter().collect::<Vec<_>>();
+        assert_eq!(
+            fetched,
+            [cachedir.join("foo-1-1-any.pkg.tar.zst").to_str().unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_fetch_pkgurl_with_retries() {
+        let cachedir = TempDir::new("fetch-pkgurl-retries");
+
+        let mut handle = Alpm::new("/", "tests/db").unwrap();
+        handle.add_cachedir(cachedir.to_str().unwrap()).unwrap();
+        handle.set_default_siglevel(SigLevel::NONE).unwrap();
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        handle.set_fetch_cb(calls.clone(), |url, path, _force, calls| {
+            calls.borrow_mut().push(url.to_string());
+            if url.contains("unreachable") {
+                return FetchResult::Err;
+            }
+            let name = url.rsplit('/').next().unwrap();
+            fs::write(format!("{}/{}", path, name), "pkg").unwrap();
+            FetchResult::Ok
+        });
+
+        let urls = [
+            "http://unreachable.invalid/foo-1-1-any.pkg.tar.zst",
+            "http://example.invalid/bar-1-1-any.pkg.tar.zst",
+        ];
+        let summary = handle.fetch_pkgurl_with_retries(urls.iter(), 2);
+        assert!(!summary.is_ok());
+        assert_eq!(
+            summary.fetched,
+            [cachedir.join("bar-1-1-any.pkg.tar.zst").to_str().unwrap()]
+        );
+        assert_eq!(summary.failed, [(urls[0].to_string(), Error::Retrieve)]);
+        let calls = calls.borrow();
+        assert_eq!(calls.iter().filter(|u| *u == urls[0]).count(), 3);
+        assert_eq!(calls.iter().filter(|u| *u == urls[1]).count(), 1);
+
+        let summary = handle.fetch_pkgurl_with_retries(urls[..1].iter(), 0);
+        assert_eq!(summary.failed.len(), 1);
+        assert!(summary.fetched.is_empty());
+    }
 }
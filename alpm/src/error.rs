@@ -1,7 +1,7 @@
+use crate::utils::from_cstr;
 use crate::Alpm;
 
 use std::error;
-use std::ffi::CStr;
 use std::fmt;
 use std::mem::transmute;
 
@@ -74,9 +74,32 @@ impl Error {
     pub(crate) unsafe fn new(err: alpm_errno_t) -> Error {
         transmute::<alpm_errno_t, Error>(err)
     }
+
+    pub fn from_raw(err: alpm_errno_t) -> Error {
+        unsafe { Error::new(err) }
+    }
+
+    /// Converts a numeric libalpm error code, returning `None` if it is not a known error.
+    pub fn from_errno(raw: u32) -> Option<Error> {
+        if raw <= Error::MissingCapabilitySignatures as u32 {
+            unsafe { Some(transmute::<u32, Error>(raw)) }
+        } else {
+            None
+        }
+    }
+
+    /// The libalpm error code.
+    pub fn raw(self) -> alpm_errno_t {
+        unsafe { transmute::<Error, alpm_errno_t>(self) }
+    }
 }
 
 impl Alpm {
+    /// The error set by the most recent failing call on this handle.
+    ///
+    /// libalpm has no way to clear the error, so this is only meaningful right after a call
+    /// failed. Returns [`Error::Ok`] if nothing has failed yet.
+    #[doc(alias = "errno")]
     pub fn last_error(&self) -> Error {
         unsafe { Error::new(alpm_errno(self.handle)) }
     }
@@ -86,13 +109,16 @@ impl Error {
     pub fn ok(self) -> bool {
         self == Error::Ok
     }
+
+    /// The message libalpm gives for this error. Same as `to_string()`.
+    pub fn strerror(self) -> &'static str {
+        unsafe { from_cstr(alpm_strerror(self.raw())) }
+    }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        let err = unsafe { transmute::<Error, alpm_errno_t>(*self) };
-        let s = unsafe { CStr::from_ptr(alpm_strerror(err)) };
-        fmt.write_str(s.to_str().unwrap())
+        fmt.write_str(self.strerror())
     }
 }
 
@@ -100,7 +126,9 @@ impl error::Error for Error {}
 
 #[cfg(test)]
 mod tests {
-    use crate::Alpm;
+    use super::*;
+    use crate::SigLevel;
+    use std::ffi::CStr;
 
     #[test]
     fn display() {
@@ -108,4 +136,63 @@ mod tests {
 
         println!("{}", handle.last_error());
     }
+
+    #[test]
+    fn display_strerror() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        let err = handle.register_syncdb("core", SigLevel::NONE).unwrap_err();
+        assert_eq!(err, Error::DbNotNull);
+
+        let expected = unsafe { CStr::from_ptr(alpm_strerror(ALPM_ERR_DB_NOT_NULL)) };
+        assert!(!err.to_string().is_empty());
+        assert_eq!(err.to_string(), expected.to_str().unwrap());
+
+        let boxed: Box<dyn error::Error> = Box::new(err);
+        assert_eq!(boxed.to_string(), err.to_string());
+    }
+
+    #[test]
+    fn last_error() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        assert_eq!(handle.last_error(), Error::Ok);
+
+        let err = handle.localdb().pkg("not-a-package").unwrap_err();
+        assert_eq!(err, Error::PkgNotFound);
+        assert_eq!(handle.last_error(), err);
+        assert_eq!(handle.last_error().strerror(), err.to_string());
+    }
+
+    #[test]
+    fn raw() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        let err = handle.register_syncdb("core", SigLevel::NONE).unwrap_err();
+        assert!(matches!(err, Error::DbNotNull));
+        assert_eq!(err.raw(), ALPM_ERR_DB_NOT_NULL);
+        assert_eq!(Error::from_raw(err.raw()), err);
+
+        assert_eq!(
+            handle.localdb().pkg("missing").unwrap_err(),
+            Error::PkgNotFound
+        );
+        assert_eq!(handle.last_error(), Error::PkgNotFound);
+        assert_eq!(err, Error::DbNotNull);
+    }
+
+    #[test]
+    fn from_errno() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let err = handle.register_syncdb("", SigLevel::NONE).unwrap_err();
+        assert_eq!(err, Error::WrongArgs);
+        assert_eq!(handle.last_error(), err);
+        assert_eq!(Error::from_errno(err.raw() as u32), Some(err));
+
+        assert_eq!(Error::from_errno(0), Some(Error::Ok));
+        assert_eq!(
+            Error::from_errno(ALPM_ERR_MISSING_CAPABILITY_SIGNATURES as u32),
+            Some(Error::MissingCapabilitySignatures)
+        );
+        assert_eq!(Error::from_errno(1000), None);
+    }
 }
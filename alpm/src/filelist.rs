@@ -3,10 +3,17 @@ use crate::Result;
 
 use alpm_sys::*;
 
-use std::ffi::CString;
+use std::cmp::Ordering;
+use std::ffi::CStr;
 use std::fmt;
+use std::marker::PhantomData;
 use std::slice;
 
+const S_IFMT: u32 = 0o170000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFREG: u32 = 0o100000;
+const S_IFLNK: u32 = 0o120000;
+
 #[repr(transparent)]
 pub struct File {
     inner: alpm_file_t,
@@ -27,56 +34,353 @@ impl File {
         unsafe { from_cstr(self.inner.name) }
     }
 
+    /// The raw bytes of the file name, which need not be UTF-8.
+    pub fn name_bytes(&self) -> &[u8] {
+        unsafe { CStr::from_ptr(self.inner.name).to_bytes() }
+    }
+
     pub fn size(&self) -> i64 {
         #[allow(clippy::useless_conversion)]
         self.inner.size.into()
     }
 
+    /// The raw mode bits of the file.
+    ///
+    /// Modes are only known for files read from a package archive. Files read from a
+    /// database have a mode of 0.
     pub fn mode(&self) -> u32 {
         self.inner.mode
     }
+
+    /// Whether this is a directory.
+    ///
+    /// Directories in file lists end with a `/`. When the mode is known it takes precedence
+    /// over the name.
+    pub fn is_dir(&self) -> bool {
+        if self.mode() == 0 {
+            self.name_bytes().ends_with(b"/")
+        } else {
+            self.mode() & S_IFMT == S_IFDIR
+        }
+    }
+
+    /// Whether this is a symlink. Always false when the mode is not known.
+    pub fn is_symlink(&self) -> bool {
+        self.mode() & S_IFMT == S_IFLNK
+    }
+
+    /// Whether this is a regular file.
+    ///
+    /// When the mode is not known this is true for anything that is not a directory, as
+    /// symlinks can not be told apart.
+    pub fn is_regular(&self) -> bool {
+        if self.mode() == 0 {
+            !self.is_dir()
+        } else {
+            self.mode() & S_IFMT == S_IFREG
+        }
+    }
+
+    /// The permission bits of the mode, including the setuid, setgid and sticky bits.
+    pub fn permissions(&self) -> u32 {
+        self.mode() & 0o7777
+    }
+
+    /// The permission bits of the mode as [`std::fs::Permissions`].
+    #[cfg(unix)]
+    pub fn fs_permissions(&self) -> std::fs::Permissions {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::Permissions::from_mode(self.permissions())
+    }
 }
 
-pub struct FileList {
-    pub(crate) inner: alpm_filelist_t,
+pub struct FileList<'a> {
+    inner: *mut alpm_filelist_t,
+    marker: PhantomData<&'a alpm_filelist_t>,
 }
 
-impl fmt::Debug for FileList {
+impl<'a> fmt::Debug for FileList<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_list().entries(self.files()).finish()
     }
 }
 
-impl FileList {
-    pub fn files(&self) -> &[File] {
-        if self.inner.files.is_null() {
-            unsafe { slice::from_raw_parts(1 as *const File, 0) }
-        } else {
-            unsafe { slice::from_raw_parts(self.inner.files as *const File, self.inner.count) }
+impl<'a> IntoIterator for &FileList<'a> {
+    type Item = &'a File;
+    type IntoIter = slice::Iter<'a, File>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.files().iter()
+    }
+}
+
+impl<'a> IntoIterator for FileList<'a> {
+    type Item = &'a File;
+    type IntoIter = slice::Iter<'a, File>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.files().iter()
+    }
+}
+
+impl<'a> FileList<'a> {
+    pub(crate) unsafe fn from_ptr(inner: *mut alpm_filelist_t) -> FileList<'a> {
+        FileList {
+            inner,
+            marker: PhantomData,
         }
     }
 
-    pub fn contains<S: Into<Vec<u8>>>(&self, path: S) -> Result<Option<File>> {
-        let path = CString::new(path).unwrap();
-        let file = unsafe {
-            alpm_filelist_contains(
-                &self.inner as *const alpm_filelist_t as *mut alpm_filelist_t,
-                path.as_ptr(),
-            )
-        };
+    pub fn len(&self) -> usize {
+        self.files().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn iter(&self) -> slice::Iter<'a, File> {
+        self.files().iter()
+    }
 
-        if file.is_null() {
-            Ok(None)
+    pub fn files(&self) -> &'a [File] {
+        let inner = unsafe { &*self.inner };
+        if inner.files.is_null() {
+            &[]
         } else {
-            let file = unsafe { *file };
-            Ok(Some(File { inner: file }))
+            unsafe { slice::from_raw_parts(inner.files as *const File, inner.count) }
         }
     }
+
+    pub fn contains<S: Into<Vec<u8>>>(&self, path: S) -> Result<Option<&'a File>> {
+        let path = cstring(path)?;
+        let file = unsafe { alpm_filelist_contains(self.inner, path.as_ptr()) };
+        Ok(unsafe { (file as *const File).as_ref() })
+    }
+
+    /// The files added, removed and kept going from this file list to `new`, e.g. from an
+    /// installed package to its upgrade.
+    ///
+    /// Paths are compared case-sensitively, ignoring a trailing `/`. Both lists must be
+    /// sorted, which is the case for lists from libalpm.
+    pub fn diff(&self, new: &FileList<'a>) -> FileListDiff<'a> {
+        let mut diff = FileListDiff::default();
+        let mut old = self.files().iter().peekable();
+        let mut new = new.files().iter().peekable();
+
+        loop {
+            match (old.peek(), new.peek()) {
+                (Some(o), Some(n)) => match path_cmp(o.name_bytes(), n.name_bytes()) {
+                    Ordering::Less => diff.removed.push(old.next().unwrap()),
+                    Ordering::Greater => diff.added.push(new.next().unwrap()),
+                    Ordering::Equal => {
+                        old.next();
+                        diff.common.push(new.next().unwrap());
+                    }
+                },
+                (Some(_), None) => diff.removed.extend(old.by_ref()),
+                (None, Some(_)) => diff.added.extend(new.by_ref()),
+                (None, None) => break,
+            }
+        }
+
+        diff
+    }
+
+    /// The files in this list that would conflict with files in `other`.
+    ///
+    /// This follows libalpm's `_alpm_filelist_intersection`: a trailing `/` is ignored when
+    /// comparing paths, and a path only conflicts if at least one side is not a directory.
+    /// Both lists must be sorted, which is the case for lists from libalpm.
+    pub fn intersect(&self, other: &FileList<'_>) -> Vec<&'a File> {
+        let mut ret = Vec::new();
+        let mut a = self.files().iter().peekable();
+        let mut b = other.files().iter().peekable();
+
+        while let (Some(fa), Some(fb)) = (a.peek(), b.peek()) {
+            match path_cmp(fa.name_bytes(), fb.name_bytes()) {
+                Ordering::Less => {
+                    a.next();
+                }
+                Ordering::Greater => {
+                    b.next();
+                }
+                Ordering::Equal => {
+                    if !fa.name_bytes().ends_with(b"/") || !fb.name_bytes().ends_with(b"/") {
+                        ret.push(*fa);
+                    }
+                    a.next();
+                    b.next();
+                }
+            }
+        }
+
+        ret
+    }
+}
+
+impl<'a> FileList<'a> {
+    /// Renders the file list as an indented directory tree.
+    ///
+    /// Each path component is shown once, indented under its parent directory.
+    /// Directories end with `/` and symlinks with `@`. Directories missing from the list
+    /// are still shown.
+    pub fn display_tree(&self) -> FileTree<'a> {
+        FileTree {
+            files: self.files(),
+        }
+    }
+}
+
+/// A file list displayed as a tree, see [`FileList::display_tree`].
+#[derive(Debug, Clone, Copy)]
+pub struct FileTree<'a> {
+    files: &'a [File],
+}
+
+impl<'a> fmt::Display for FileTree<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut dirs: Vec<&str> = Vec::new();
+
+        for file in self.files {
+            let path = file.name().trim_end_matches('/');
+            let (parents, name) = match path.rfind('/') {
+                Some(i) => (&path[..i], &path[i + 1..]),
+                None => ("", path),
+            };
+            let parents = parents.split('/').filter(|p| !p.is_empty());
+
+            let mut depth = 0;
+            for parent in parents {
+                if dirs.get(depth) != Some(&parent) {
+                    dirs.truncate(depth);
+                    writeln!(f, "{:indent$}{}/", "", parent, indent = depth * 2)?;
+                    dirs.push(parent);
+                }
+                depth += 1;
+            }
+            dirs.truncate(depth);
+
+            let suffix = if file.is_dir() {
+                "/"
+            } else if file.is_symlink() {
+                "@"
+            } else {
+                ""
+            };
+            writeln!(f, "{:indent$}{}{}", "", name, suffix, indent = depth * 2)?;
+            if file.is_dir() {
+                dirs.push(name);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Compares paths like `strcmp`, except that a trailing `/` is ignored.
+fn path_cmp(a: &[u8], b: &[u8]) -> Ordering {
+    let common = a.iter().zip(b).take_while(|(a, b)| a == b).count();
+    let (mut a, mut b) = (&a[common..], &b[common..]);
+
+    if a.is_empty() && b == b"/" {
+        b = &b[1..];
+    } else if b.is_empty() && a == b"/" {
+        a = &a[1..];
+    }
+
+    a.cmp(b)
+}
+
+/// The difference between two file lists, see [`FileList::diff`].
+#[derive(Debug, Default, Clone)]
+pub struct FileListDiff<'a> {
+    added: Vec<&'a File>,
+    removed: Vec<&'a File>,
+    common: Vec<&'a File>,
+}
+
+impl<'a> FileListDiff<'a> {
+    /// Files only in the new list.
+    pub fn added(&self) -> &[&'a File] {
+        &self.added
+    }
+
+    /// Files only in the old list.
+    pub fn removed(&self) -> &[&'a File] {
+        &self.removed
+    }
+
+    /// Files in both lists, as they are in the new list.
+    pub fn common(&self) -> &[&'a File] {
+        &self.common
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
     use crate::{Alpm, SigLevel};
+    use std::ffi::CString;
+    use std::ptr;
+
+    fn with_filelist<F: FnOnce(FileList)>(names: &[&str], f: F) {
+        let files = names.iter().map(|n| (*n, 0)).collect::<Vec<_>>();
+        with_filelist_modes(&files, f)
+    }
+
+    fn with_filelist_modes<F: FnOnce(FileList)>(files: &[(&str, u32)], f: F) {
+        let names = files
+            .iter()
+            .map(|(n, _)| CString::new(*n).unwrap())
+            .collect::<Vec<_>>();
+        let mut files = names
+            .iter()
+            .zip(files)
+            .map(|(n, (_, mode))| alpm_file_t {
+                name: n.as_ptr() as *mut _,
+                size: 0,
+                mode: *mode,
+            })
+            .collect::<Vec<_>>();
+        let mut list = alpm_filelist_t {
+            count: files.len(),
+            files: if files.is_empty() {
+                ptr::null_mut()
+            } else {
+                files.as_mut_ptr()
+            },
+        };
+        f(unsafe { FileList::from_ptr(&mut list) });
+    }
+
+    #[test]
+    fn test_files_intersect() {
+        fn names(files: Vec<&File>) -> Vec<&str> {
+            files.iter().map(|f| f.name()).collect()
+        }
+
+        with_filelist(&["usr/", "usr/lib/", "usr/lib/foo/"], |a| {
+            with_filelist(&["usr/", "usr/lib/", "usr/lib/foo"], |b| {
+                assert_eq!(names(a.intersect(&b)), ["usr/lib/foo/"]);
+                assert_eq!(names(b.intersect(&a)), ["usr/lib/foo"]);
+            });
+        });
+
+        with_filelist(&["etc/", "etc/foo.conf", "usr/bin/foo"], |a| {
+            with_filelist(&["etc/", "etc/bar.conf", "usr/bin/foo"], |b| {
+                assert_eq!(names(a.intersect(&b)), ["usr/bin/foo"]);
+            });
+            with_filelist(&[], |b| assert!(a.intersect(&b).is_empty()));
+        });
+
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let bash = handle.localdb().pkg("bash").unwrap().files();
+        let linux = handle.localdb().pkg("linux").unwrap().files();
+        assert!(bash.intersect(&linux).is_empty());
+        let own = bash.intersect(&bash);
+        assert_eq!(own.len(), bash.iter().filter(|f| !f.is_dir()).count());
+    }
 
     #[test]
     fn test_files() {
@@ -87,6 +391,7 @@ mod tests {
 
         assert!(files.files().is_empty());
         assert!(Some(files.files()).is_some());
+        assert!(pkg.try_files().is_none());
 
         let db = handle.localdb();
         let pkg = db.pkg("linux").unwrap();
@@ -99,4 +404,201 @@ mod tests {
         assert_eq!(file.name(), "boot/");
         assert!(files.contains("aaaaa/").unwrap().is_none());
     }
+
+    #[test]
+    fn test_files_borrowed() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let files = handle.localdb().pkg("filesystem").unwrap().files();
+        let file = files.contains("etc/fstab").unwrap().unwrap();
+        assert!(files.files().iter().any(|f| std::ptr::eq(f, file)));
+        assert_eq!(file.name(), "etc/fstab");
+    }
+
+    #[test]
+    fn test_files_diff() {
+        fn has(files: &[&File], name: &str) -> bool {
+            files.iter().any(|f| f.name() == name)
+        }
+
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let db = handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        let sync = db.pkg("linux").unwrap().files();
+        let local = handle.localdb().pkg("linux").unwrap().files();
+
+        let diff = local.diff(&sync);
+        assert!(diff.added().is_empty());
+        assert!(diff.common().is_empty());
+        assert_eq!(diff.removed().len(), local.len());
+        assert_eq!(diff.removed()[0].name(), local.files()[0].name());
+
+        let diff = sync.diff(&local);
+        assert_eq!(diff.added().len(), local.len());
+        assert!(diff.removed().is_empty());
+
+        let diff = local.diff(&local);
+        assert!(diff.added().is_empty() && diff.removed().is_empty());
+        assert_eq!(diff.common().len(), local.len());
+
+        let bash = handle.localdb().pkg("bash").unwrap().files();
+        let diff = local.diff(&bash);
+        assert!(has(diff.added(), "usr/bin/bash"));
+        assert!(has(diff.removed(), "boot/"));
+        assert!(has(diff.common(), "usr/"));
+        assert!(!has(diff.added(), "usr/") && !has(diff.removed(), "usr/"));
+
+        with_filelist(&["usr/lib/foo/", "usr/lib/foo/a"], |old| {
+            with_filelist(&["usr/lib/Foo", "usr/lib/foo"], |new| {
+                let diff = old.diff(&new);
+                assert!(has(diff.added(), "usr/lib/Foo"));
+                assert!(has(diff.common(), "usr/lib/foo"));
+                assert!(has(diff.removed(), "usr/lib/foo/a"));
+            });
+        });
+    }
+
+    #[test]
+    fn test_files_diff_loaded() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let loaded = handle
+            .pkg_load(
+                "tests/pacman-5.1.3-1-x86_64.pkg.tar.xz",
+                true,
+                SigLevel::NONE,
+            )
+            .unwrap();
+        let local = handle.localdb().pkg("pacman").unwrap();
+
+        // The fixture package only contains its metadata.
+        assert!(loaded.files().is_empty());
+        let diff = local.files().diff(&loaded.files());
+        assert!(diff.added().is_empty());
+        assert!(diff.common().is_empty());
+        assert_eq!(diff.removed().len(), local.files().len());
+
+        let diff = loaded.files().diff(&local.files());
+        assert_eq!(diff.added().len(), local.files().len());
+        assert!(diff.removed().is_empty());
+    }
+
+    #[test]
+    fn test_files_iter() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let db = handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        let pkg = db.pkg("linux").unwrap();
+        let files = pkg.files();
+        assert!(files.is_empty());
+        assert_eq!(files.iter().count(), 0);
+
+        let pkg = handle.localdb().pkg("linux").unwrap();
+        let files = pkg.files();
+        assert!(!files.is_empty());
+        assert_eq!(files.len(), files.files().len());
+
+        let mut count = 0;
+        for file in &files {
+            assert!(!file.name().is_empty());
+            count += 1;
+        }
+        assert_eq!(count, files.len());
+        assert!(files.iter().any(|f| f.name() == "boot/"));
+    }
+
+    #[test]
+    fn test_file_type() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let pkg = handle.localdb().pkg("filesystem").unwrap();
+        let files = pkg.files();
+
+        let etc = files.contains("etc/").unwrap().unwrap();
+        assert!(etc.is_dir());
+        assert!(!etc.is_symlink());
+        assert!(!etc.is_regular());
+
+        let fstab = files.contains("etc/fstab").unwrap().unwrap();
+        assert!(!fstab.is_dir());
+        assert!(!fstab.is_symlink());
+        assert!(fstab.is_regular());
+        assert_eq!(fstab.mode(), 0);
+        assert_eq!(fstab.permissions(), 0);
+
+        let linux = handle.localdb().pkg("linux").unwrap();
+        let boot = linux.files().contains("boot/").unwrap().unwrap();
+        assert!(boot.is_dir() && !boot.is_regular());
+    }
+
+    #[test]
+    fn test_file_mode() {
+        let file = |name: &CString, mode| File {
+            inner: alpm_file_t {
+                name: name.as_ptr() as *mut _,
+                size: 0,
+                mode,
+            },
+        };
+        let bin = CString::new("usr/bin/passwd").unwrap();
+        let link = CString::new("usr/lib/libfoo.so").unwrap();
+        let dir = CString::new("tmp/").unwrap();
+
+        let passwd = file(&bin, 0o104755);
+        assert!(passwd.is_regular() && !passwd.is_dir() && !passwd.is_symlink());
+        assert_eq!(passwd.permissions(), 0o4755);
+
+        let so = file(&link, 0o120777);
+        assert!(so.is_symlink() && !so.is_regular() && !so.is_dir());
+
+        let tmp = file(&dir, 0o041777);
+        assert!(tmp.is_dir() && !tmp.is_regular());
+        assert_eq!(tmp.permissions(), 0o1777);
+
+        // The mode wins over the trailing slash.
+        let odd = file(&dir, 0o100644);
+        assert!(!odd.is_dir() && odd.is_regular());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(passwd.fs_permissions().mode(), 0o4755);
+            assert!(!passwd.fs_permissions().readonly());
+        }
+    }
+
+    #[test]
+    fn test_display_tree() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let pkg = handle.localdb().pkg("hwids").unwrap();
+        assert_eq!(
+            pkg.files().display_tree().to_string(),
+            "usr/\n  share/\n    hwdata/\n      pci.ids\n      usb.ids\n"
+        );
+
+        let files = [
+            ("etc/foo.conf", 0o100644),
+            ("usr/", 0o040755),
+            ("usr/bin/foo", 0o100755),
+            ("usr/bin/foo-link", 0o120777),
+            ("usr/lib/", 0o040755),
+            ("usr/lib/libfoo.so", 0o100755),
+            ("usr/share/doc/foo/README", 0o100644),
+        ];
+        with_filelist_modes(&files, |list| {
+            let expected = [
+                "etc/",
+                "  foo.conf",
+                "usr/",
+                "  bin/",
+                "    foo",
+                "    foo-link@",
+                "  lib/",
+                "    libfoo.so",
+                "  share/",
+                "    doc/",
+                "      foo/",
+                "        README",
+                "",
+            ];
+            assert_eq!(list.display_tree().to_string(), expected.join("\n"));
+        });
+
+        with_filelist(&[], |list| assert_eq!(list.display_tree().to_string(), ""));
+    }
 }
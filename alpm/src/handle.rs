@@ -5,7 +5,23 @@ use crate::{
 
 use alpm_sys::*;
 use std::cmp::Ordering;
-use std::ffi::CString;
+use std::fmt;
+use std::path::Path;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// Returned by [`wait_lock`](Alpm::wait_lock) when the database is still locked after the
+/// timeout.
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Ord, PartialOrd, Hash)]
+pub struct WaitLockError;
+
+impl fmt::Display for WaitLockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Timed out waiting for the database lock")
+    }
+}
+
+impl std::error::Error for WaitLockError {}
 
 impl Alpm {
     pub fn as_alpm_handle_t(&self) -> *mut alpm_handle_t {
@@ -17,6 +33,43 @@ impl Alpm {
         self.check_ret(ret)
     }
 
+    /// Whether the [`lockfile`](Alpm::lockfile) exists, meaning another process, or this
+    /// handle, holds the database lock.
+    ///
+    /// The lock may be taken or released as soon as this returns, so this is only useful
+    /// for informing the user. Initialising a transaction is what actually takes the lock.
+    pub fn is_locked(&self) -> bool {
+        Path::new(self.lockfile()).exists()
+    }
+
+    /// Waits until the database is not locked, checking every `poll`.
+    ///
+    /// Fails with [`WaitLockError`] if the database is still locked after `timeout`. A
+    /// `timeout` of `None` waits forever. Like [`is_locked`](Alpm::is_locked), another
+    /// process can take the lock before a transaction is initialised, so the result of
+    /// [`trans_init`](Alpm::trans_init) must still be checked.
+    pub fn wait_lock(
+        &self,
+        timeout: Option<Duration>,
+        poll: Duration,
+    ) -> std::result::Result<(), WaitLockError> {
+        let start = Instant::now();
+
+        while self.is_locked() {
+            if let Some(timeout) = timeout {
+                let elapsed = start.elapsed();
+                if elapsed >= timeout {
+                    return Err(WaitLockError);
+                }
+                sleep(poll.min(timeout - elapsed));
+            } else {
+                sleep(poll);
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn root(&self) -> &str {
         unsafe { from_cstr(alpm_option_get_root(self.handle)) }
     }
@@ -86,12 +139,16 @@ impl Alpm {
         unsafe { alpm_option_get_checkspace(self.handle) != 0 }
     }
 
+    pub fn parallel_downloads(&self) -> u32 {
+        unsafe { alpm_option_get_parallel_downloads(self.handle) as u32 }
+    }
+
     pub fn dbext(&self) -> &str {
         unsafe { from_cstr(alpm_option_get_dbext(self.handle)) }
     }
 
     pub fn add_hookdir<S: Into<Vec<u8>>>(&mut self, s: S) -> Result<()> {
-        let s = CString::new(s).unwrap();
+        let s = cstring(s)?;
         let ret = unsafe { alpm_option_add_hookdir(self.handle, s.as_ptr()) };
         self.check_ret(ret)
     }
@@ -103,7 +160,7 @@ impl Alpm {
     }
 
     pub fn remove_hookdir<S: Into<Vec<u8>>>(&mut self, s: S) -> Result<bool> {
-        let s = CString::new(s).unwrap();
+        let s = cstring(s)?;
         let ret = unsafe { alpm_option_remove_hookdir(self.handle, s.as_ptr()) };
         if ret == 1 {
             Ok(true)
@@ -113,7 +170,7 @@ impl Alpm {
     }
 
     pub fn add_cachedir<S: Into<Vec<u8>>>(&mut self, s: S) -> Result<()> {
-        let s = CString::new(s).unwrap();
+        let s = cstring(s)?;
         let ret = unsafe { alpm_option_add_cachedir(self.handle, s.as_ptr()) };
         self.check_ret(ret)
     }
@@ -125,7 +182,7 @@ impl Alpm {
     }
 
     pub fn remove_cachedir<S: Into<Vec<u8>>>(&mut self, s: S) -> Result<bool> {
-        let s = CString::new(s).unwrap();
+        let s = cstring(s)?;
         let ret = unsafe { alpm_option_remove_cachedir(self.handle, s.as_ptr()) };
         if ret == 1 {
             Ok(true)
@@ -139,13 +196,13 @@ impl Alpm {
     }
 
     pub fn set_logfile<S: Into<Vec<u8>>>(&self, s: S) -> Result<()> {
-        let s = CString::new(s).unwrap();
+        let s = cstring(s)?;
         let ret = unsafe { alpm_option_set_logfile(self.handle, s.as_ptr()) };
         self.check_ret(ret)
     }
 
     pub fn set_gpgdir<S: Into<Vec<u8>>>(&self, s: S) -> Result<()> {
-        let s = CString::new(s).unwrap();
+        let s = cstring(s)?;
         let ret = unsafe { alpm_option_set_gpgdir(self.handle, s.as_ptr()) };
         self.check_ret(ret)
     }
@@ -156,7 +213,7 @@ impl Alpm {
     }
 
     pub fn add_noupgrade<S: Into<Vec<u8>>>(&mut self, s: S) -> Result<()> {
-        let s = CString::new(s).unwrap();
+        let s = cstring(s)?;
         let ret = unsafe { alpm_option_add_noupgrade(self.handle, s.as_ptr()) };
         self.check_ret(ret)
     }
@@ -168,7 +225,7 @@ impl Alpm {
     }
 
     pub fn remove_noupgrade<S: Into<Vec<u8>>>(&mut self, s: S) -> Result<bool> {
-        let s = CString::new(s).unwrap();
+        let s = cstring(s)?;
         let ret = unsafe { alpm_option_remove_noupgrade(self.handle, s.as_ptr()) };
         if ret == 1 {
             Ok(true)
@@ -177,19 +234,22 @@ impl Alpm {
         }
     }
 
-    pub fn match_noupgrade<S: Into<Vec<u8>>>(&mut self, s: S) -> Match {
-        let s = CString::new(s).unwrap();
+    /// Matches a path, relative to the root, against the NoUpgrade patterns.
+    ///
+    /// Fails with [`Error::WrongArgs`] if the path contains a nul byte.
+    pub fn match_noupgrade<S: Into<Vec<u8>>>(&mut self, s: S) -> Result<Match> {
+        let s = cstring(s)?;
         let ret = unsafe { alpm_option_match_noupgrade(self.handle, s.as_ptr()) };
 
         match ret.cmp(&0) {
-            Ordering::Equal => Match::Yes,
-            Ordering::Greater => Match::Inverted,
-            Ordering::Less => Match::No,
+            Ordering::Equal => Ok(Match::Yes),
+            Ordering::Greater => Ok(Match::Inverted),
+            Ordering::Less => Ok(Match::No),
         }
     }
 
     pub fn add_noextract<S: Into<Vec<u8>>>(&mut self, s: S) -> Result<()> {
-        let s = CString::new(s).unwrap();
+        let s = cstring(s)?;
         let ret = unsafe { alpm_option_add_noextract(self.handle, s.as_ptr()) };
         self.check_ret(ret)
     }
@@ -201,7 +261,7 @@ impl Alpm {
     }
 
     pub fn remove_noextract<S: Into<Vec<u8>>>(&mut self, s: S) -> Result<bool> {
-        let s = CString::new(s).unwrap();
+        let s = cstring(s)?;
         let ret = unsafe { alpm_option_remove_noextract(self.handle, s.as_ptr()) };
         if ret == 1 {
             Ok(true)
@@ -210,19 +270,22 @@ impl Alpm {
         }
     }
 
-    pub fn match_noextract<S: Into<Vec<u8>>>(&mut self, s: S) -> Match {
-        let s = CString::new(s).unwrap();
+    /// Matches a path, relative to the root, against the NoExtract patterns.
+    ///
+    /// Fails with [`Error::WrongArgs`] if the path contains a nul byte.
+    pub fn match_noextract<S: Into<Vec<u8>>>(&mut self, s: S) -> Result<Match> {
+        let s = cstring(s)?;
         let ret = unsafe { alpm_option_match_noextract(self.handle, s.as_ptr()) };
 
         match ret.cmp(&0) {
-            Ordering::Equal => Match::Yes,
-            Ordering::Greater => Match::Inverted,
-            Ordering::Less => Match::No,
+            Ordering::Equal => Ok(Match::Yes),
+            Ordering::Greater => Ok(Match::Inverted),
+            Ordering::Less => Ok(Match::No),
         }
     }
 
     pub fn add_ignorepkg<S: Into<Vec<u8>>>(&mut self, s: S) -> Result<()> {
-        let s = CString::new(s).unwrap();
+        let s = cstring(s)?;
         let ret = unsafe { alpm_option_add_ignorepkg(self.handle, s.as_ptr()) };
         self.check_ret(ret)
     }
@@ -234,7 +297,7 @@ impl Alpm {
     }
 
     pub fn remove_ignorepkg<S: Into<Vec<u8>>>(&mut self, s: S) -> Result<bool> {
-        let s = CString::new(s).unwrap();
+        let s = cstring(s)?;
         let ret = unsafe { alpm_option_remove_ignorepkg(self.handle, s.as_ptr()) };
         if ret == 1 {
             Ok(true)
@@ -244,7 +307,7 @@ impl Alpm {
     }
 
     pub fn add_ignoregroup<S: Into<Vec<u8>>>(&mut self, s: S) -> Result<()> {
-        let s = CString::new(s).unwrap();
+        let s = cstring(s)?;
         let ret = unsafe { alpm_option_add_ignoregroup(self.handle, s.as_ptr()) };
         self.check_ret(ret)
     }
@@ -259,7 +322,7 @@ impl Alpm {
     }
 
     pub fn remove_ignoregroup<S: Into<Vec<u8>>>(&mut self, s: S) -> Result<bool> {
-        let s = CString::new(s).unwrap();
+        let s = cstring(s)?;
         let ret = unsafe { alpm_option_remove_ignoregroup(self.handle, s.as_ptr()) };
         if ret == 1 {
             Ok(true)
@@ -269,7 +332,7 @@ impl Alpm {
     }
 
     pub fn add_overwrite_file<S: Into<Vec<u8>>>(&mut self, s: S) -> Result<()> {
-        let s = CString::new(s).unwrap();
+        let s = cstring(s)?;
         let ret = unsafe { alpm_option_add_overwrite_file(self.handle, s.as_ptr()) };
         self.check_ret(ret)
     }
@@ -284,7 +347,7 @@ impl Alpm {
     }
 
     pub fn remove_overwrite_file<S: Into<Vec<u8>>>(&mut self, s: S) -> Result<bool> {
-        let s = CString::new(s).unwrap();
+        let s = cstring(s)?;
         let ret = unsafe { alpm_option_remove_overwrite_file(self.handle, s.as_ptr()) };
         if ret == 1 {
             Ok(true)
@@ -293,13 +356,18 @@ impl Alpm {
         }
     }
 
-    pub fn add_assume_installed(&mut self, s: &Dep) -> Result<()> {
-        let ret = unsafe { alpm_option_add_assumeinstalled(self.handle, s.inner) };
+    /// Adds a dependency to treat as installed. libalpm keeps its own copy of `s`.
+    pub fn add_assume_installed<D: AsDep>(&mut self, s: D) -> Result<()> {
+        let ret = unsafe { alpm_option_add_assumeinstalled(self.handle, s.as_dep().inner) };
         self.check_ret(ret)
     }
 
+    /// Replaces the dependencies to treat as installed.
+    ///
+    /// Accepts iterators over [`Depend`] or [`Dep`] values or references. libalpm copies
+    /// each dependency so the list may be dropped afterwards.
     pub fn set_assume_installed<'a, T: IntoRawAlpmList<'a, Dep<'a>>>(
-        &'a mut self,
+        &mut self,
         list: T,
     ) -> Result<()> {
         let list = unsafe { list.into_raw_alpm_list() };
@@ -317,7 +385,7 @@ impl Alpm {
     }
 
     pub fn add_architecture<S: Into<Vec<u8>>>(&mut self, s: S) -> Result<()> {
-        let s = CString::new(s).unwrap();
+        let s = cstring(s)?;
         let ret = unsafe { alpm_option_add_architecture(self.handle, s.as_ptr()) };
         self.check_ret(ret)
     }
@@ -332,7 +400,7 @@ impl Alpm {
     }
 
     pub fn remove_architecture<S: Into<Vec<u8>>>(&mut self, s: S) -> Result<bool> {
-        let s = CString::new(s).unwrap();
+        let s = cstring(s)?;
         let ret = unsafe { alpm_option_remove_architecture(self.handle, s.as_ptr()) };
         if ret == 1 {
             Ok(true)
@@ -346,6 +414,7 @@ impl Alpm {
         Db { handle: self, db }
     }
 
+    /// The registered sync databases, in the order they were registered.
     pub fn syncdbs(&self) -> AlpmList<Db> {
         let dbs = unsafe { alpm_get_syncdbs(self.handle) };
         AlpmList::from_parts(self, dbs)
@@ -361,9 +430,10 @@ impl Alpm {
         unsafe { alpm_option_set_checkspace(self.handle, b) };
     }
 
-    pub fn set_dbext<S: Into<Vec<u8>>>(&self, s: S) {
-        let s = CString::new(s).unwrap();
-        unsafe { alpm_option_set_dbext(self.handle, s.as_ptr()) };
+    pub fn set_dbext<S: Into<Vec<u8>>>(&self, s: S) -> Result<()> {
+        let s = cstring(s)?;
+        let ret = unsafe { alpm_option_set_dbext(self.handle, s.as_ptr()) };
+        self.check_ret(ret)
     }
 
     pub fn set_default_siglevel(&self, s: SigLevel) -> Result<()> {
@@ -373,7 +443,7 @@ impl Alpm {
 
     pub fn default_siglevel(&self) -> SigLevel {
         let ret = unsafe { alpm_option_get_default_siglevel(self.handle) };
-        SigLevel::from_bits(ret as u32).unwrap()
+        SigLevel::from_raw(ret)
     }
 
     pub fn set_local_file_siglevel(&self, s: SigLevel) -> Result<()> {
@@ -383,7 +453,7 @@ impl Alpm {
 
     pub fn local_file_siglevel(&self) -> SigLevel {
         let ret = unsafe { alpm_option_get_local_file_siglevel(self.handle) };
-        SigLevel::from_bits(ret as u32).unwrap()
+        SigLevel::from_raw(ret)
     }
 
     pub fn set_remote_file_siglevel(&self, s: SigLevel) -> Result<()> {
@@ -393,9 +463,10 @@ impl Alpm {
 
     pub fn remote_file_siglevel(&self) -> SigLevel {
         let ret = unsafe { alpm_option_get_remote_file_siglevel(self.handle) };
-        SigLevel::from_bits(ret as u32).unwrap()
+        SigLevel::from_raw(ret)
     }
 
+    /// libalpm has no getter for this option.
     pub fn set_disable_dl_timeout(&self, b: bool) {
         let b = if b { 1 } else { 0 };
         unsafe { alpm_option_set_disable_dl_timeout(self.handle, b) };
@@ -404,11 +475,105 @@ impl Alpm {
     pub fn set_parallel_downloads(&self, n: u32) {
         unsafe { alpm_option_set_parallel_downloads(self.handle, n) };
     }
+
+    /// The user libalpm switches to when downloading, pacman's `DownloadUser`.
+    #[cfg(feature = "git")]
+    pub fn sandbox_user(&self) -> Option<&str> {
+        unsafe { from_cstr_optional(alpm_option_get_sandboxuser(self.handle)) }
+    }
+
+    /// Sets the user to switch to when downloading, or `None` to download as the current
+    /// user.
+    ///
+    /// libalpm only looks the user up when a download starts, so a nonexistent user is
+    /// reported by the download failing rather than here.
+    #[cfg(feature = "git")]
+    pub fn set_sandbox_user<S: Into<Vec<u8>>>(&self, user: Option<S>) -> Result<()> {
+        let user = user.map(cstring).transpose()?;
+        let user = user.as_ref().map_or(std::ptr::null(), |u| u.as_ptr());
+        let ret = unsafe { alpm_option_set_sandboxuser(self.handle, user) };
+        self.check_ret(ret)
+    }
+
+    /// Whether the download sandbox is disabled, pacman's `DisableSandbox`.
+    #[cfg(feature = "git")]
+    pub fn disable_sandbox(&self) -> bool {
+        unsafe { alpm_option_get_disable_sandbox(self.handle) != 0 }
+    }
+
+    #[cfg(feature = "git")]
+    pub fn set_disable_sandbox(&self, b: bool) -> Result<()> {
+        let ret = unsafe { alpm_option_set_disable_sandbox(self.handle, b as _) };
+        self.check_ret(ret)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Error;
+
+    #[test]
+    #[cfg(feature = "git")]
+    fn test_sandbox() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        assert_eq!(handle.sandbox_user(), None);
+        assert!(!handle.disable_sandbox());
+
+        handle.set_sandbox_user(Some("alpm")).unwrap();
+        assert_eq!(handle.sandbox_user(), Some("alpm"));
+        handle.set_sandbox_user(None::<&str>).unwrap();
+        assert_eq!(handle.sandbox_user(), None);
+        assert_eq!(handle.set_sandbox_user(Some("a\0b")), Err(Error::WrongArgs));
+
+        handle.set_disable_sandbox(true).unwrap();
+        assert!(handle.disable_sandbox());
+    }
+
+    #[test]
+    fn test_wait_lock() {
+        let tmp = TempDir::new("wait-lock");
+        let handle = Alpm::new("/", tmp.to_str().unwrap()).unwrap();
+        let poll = Duration::from_millis(5);
+
+        assert!(!handle.is_locked());
+        handle
+            .wait_lock(Some(Duration::from_secs(0)), poll)
+            .unwrap();
+
+        let lockfile = handle.lockfile().to_string();
+        std::fs::write(&lockfile, "").unwrap();
+        assert!(handle.is_locked());
+        let err = handle.wait_lock(Some(Duration::from_millis(20)), poll);
+        assert_eq!(err, Err(WaitLockError));
+        assert_eq!(
+            err.unwrap_err().to_string(),
+            "Timed out waiting for the database lock"
+        );
+
+        let remover = std::thread::spawn(move || {
+            sleep(Duration::from_millis(20));
+            std::fs::remove_file(lockfile).unwrap();
+        });
+        handle.wait_lock(None, poll).unwrap();
+        assert!(!handle.is_locked());
+        remover.join().unwrap();
+    }
+
+    #[test]
+    fn test_syncdbs_order() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        for name in &["extra", "core", "community", "testing"] {
+            handle.register_syncdb(*name, SigLevel::NONE).unwrap();
+        }
+
+        let names = handle
+            .syncdbs()
+            .iter()
+            .map(|db| db.name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["extra", "core", "community", "testing"]);
+    }
 
     #[test]
     fn test_getters() {
@@ -443,6 +608,10 @@ mod tests {
         handle.set_check_space(false);
         assert!(!handle.check_space());
 
+        assert_eq!(handle.parallel_downloads(), 1);
+        handle.set_parallel_downloads(5);
+        assert_eq!(handle.parallel_downloads(), 5);
+
         assert_eq!(handle.default_siglevel(), SigLevel::NONE);
         handle
             .set_default_siglevel(SigLevel::PACKAGE | SigLevel::DATABASE)
@@ -456,12 +625,130 @@ mod tests {
         let pkgs = handle.ignorepkgs().iter().collect::<Vec<_>>();
         assert_eq!(pkgs.as_slice(), ["a", "b", "c"]);
 
-        /*let indeps = vec!["a", "b", "c"].into_iter().map(|s| Depend::new(s)).collect::<Vec<_>>();
-        let deps = vec!["a", "b", "c"].into_iter().map(|s| Depend::new(s)).collect::<Vec<_>>();
-        handle.set_assume_installed(indeps);
+        let deps = ["a", "b>1", "c=2"].iter().map(|s| Depend::new(*s));
+        let deps = deps.collect::<Vec<_>>();
+        handle.set_assume_installed(deps.iter()).unwrap();
+        let ai = handle.assume_installed();
+        let ai = ai.iter().map(|d| d.to_string()).collect::<Vec<_>>();
+        assert_eq!(ai, ["a", "b>1", "c=2"]);
+    }
+
+    #[test]
+    fn test_assume_installed_owned() {
+        let mut handle = Alpm::new("/", "tests/db/").unwrap();
+
+        let deps = ["foo", "bar>=1", "baz=2-1"].iter().map(|s| Depend::new(*s));
+        handle.set_assume_installed(deps).unwrap();
+
+        let extra = Depend::new("qux<3");
+        handle.add_assume_installed(&extra).unwrap();
+        drop(extra);
+        handle.add_assume_installed(Depend::new("quux")).unwrap();
+
+        let ai = handle.assume_installed();
+        let ai = ai.iter().map(|d| d.to_string()).collect::<Vec<_>>();
+        assert_eq!(ai, ["foo", "bar>=1", "baz=2-1", "qux<3", "quux"]);
+
+        assert!(handle.remove_assume_installed(Depend::new("quux")).unwrap());
+        handle
+            .set_assume_installed([Depend::new("only")].iter())
+            .unwrap();
+        let ai = handle.assume_installed();
+        assert_eq!(ai.first().unwrap().to_string(), "only");
+        assert_eq!(ai.len(), 1);
+    }
+
+    #[test]
+    fn test_match_patterns() {
+        let mut handle = Alpm::new("/", "tests/db").unwrap();
+        handle.add_noupgrade("etc/*").unwrap();
+        handle.add_noupgrade("!etc/pacman.conf").unwrap();
+        handle.add_noextract("usr/share/doc/*").unwrap();
+        handle.add_noextract("!usr/share/doc/pacman/*").unwrap();
+
+        assert_eq!(handle.match_noupgrade("etc/fstab").unwrap(), Match::Yes);
+        assert_eq!(
+            handle.match_noupgrade("etc/pacman.conf").unwrap(),
+            Match::Inverted
+        );
+        assert_eq!(handle.match_noupgrade("usr/bin/pacman").unwrap(), Match::No);
+
+        assert_eq!(
+            handle.match_noextract("usr/share/doc/bash/README").unwrap(),
+            Match::Yes
+        );
+        assert_eq!(
+            handle
+                .match_noextract("usr/share/doc/pacman/README")
+                .unwrap(),
+            Match::Inverted
+        );
+        assert_eq!(
+            handle.match_noextract("etc/pacman.conf").unwrap(),
+            Match::No
+        );
+    }
+
+    #[test]
+    fn test_nul() {
+        let nul = "foo\0bar";
+        assert_eq!(Alpm::new(nul, "tests/db").unwrap_err(), Error::WrongArgs);
+
+        let mut handle = Alpm::new("/", "tests/db").unwrap();
+        assert_eq!(handle.add_hookdir(nul), Err(Error::WrongArgs));
+        assert_eq!(handle.set_logfile(nul), Err(Error::WrongArgs));
+        assert_eq!(handle.add_ignorepkg(nul), Err(Error::WrongArgs));
+        assert_eq!(handle.remove_ignorepkg(nul), Err(Error::WrongArgs));
+        assert_eq!(handle.set_dbext(nul), Err(Error::WrongArgs));
+        assert_eq!(handle.match_noupgrade(nul), Err(Error::WrongArgs));
+        assert_eq!(handle.match_noextract(nul), Err(Error::WrongArgs));
+        assert_eq!(handle.log_action(nul, "msg"), Err(Error::WrongArgs));
+        assert!(handle.ignorepkgs().is_empty());
+
+        let db = handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        assert_eq!(db.pkg(nul).unwrap_err(), Error::WrongArgs);
+        assert_eq!(db.group(nul).unwrap_err(), Error::WrongArgs);
+        assert!(db.pkgs().find_satisfier(nul).is_none());
+        assert!(handle.syncdbs().find_satisfier(nul).is_none());
+        assert_eq!(
+            handle.find_group_pkgs(handle.syncdbs(), nul).unwrap_err(),
+            Error::WrongArgs
+        );
+        assert_eq!(
+            handle.register_syncdb(nul, SigLevel::NONE).unwrap_err(),
+            Error::WrongArgs
+        );
+
+        let pkg = handle.localdb().pkg("filesystem").unwrap();
+        assert_eq!(pkg.files().contains(nul).unwrap_err(), Error::WrongArgs);
+    }
+
+    #[test]
+    fn test_siglevel_use_default() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        assert_eq!(
+            handle.set_default_siglevel(SigLevel::USE_DEFAULT),
+            Err(Error::WrongArgs)
+        );
 
-        let ai = handle.assume_installed().collect::<Vec<_>>();
-        assert_eq!(deps.into_iter().map(|d| d.to_string()).collect::<Vec<_>>(), ai.into_iter().map(|d| d.to_string()).collect::<Vec<_>>());
-        */
+        handle.set_default_siglevel(SigLevel::PACKAGE).unwrap();
+        handle
+            .set_local_file_siglevel(SigLevel::USE_DEFAULT)
+            .unwrap();
+        handle
+            .set_remote_file_siglevel(SigLevel::USE_DEFAULT)
+            .unwrap();
+        assert_eq!(handle.local_file_siglevel(), SigLevel::PACKAGE);
+        assert_eq!(handle.remote_file_siglevel(), SigLevel::PACKAGE);
+
+        let db = handle
+            .register_syncdb("core", SigLevel::USE_DEFAULT)
+            .unwrap();
+        assert_eq!(db.siglevel(), SigLevel::PACKAGE);
+
+        handle
+            .set_local_file_siglevel(SigLevel::PACKAGE_OPTIONAL)
+            .unwrap();
+        assert_eq!(handle.local_file_siglevel(), SigLevel::PACKAGE_OPTIONAL);
     }
 }
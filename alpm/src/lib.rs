@@ -4,6 +4,7 @@ mod be_local;
 mod be_pkg;
 mod be_sync;
 mod cb;
+mod config;
 mod conflict;
 mod db;
 mod deps;
@@ -17,12 +18,15 @@ mod log;
 mod mtree;
 mod package;
 mod remove;
+#[cfg(feature = "serde")]
+mod ser;
 mod signing;
 mod sync;
 mod trans;
 mod types;
 mod util;
 mod utils;
+mod xfer;
 
 mod version;
 
@@ -32,6 +36,7 @@ pub use crate::be_local::*;
 pub use crate::be_pkg::*;
 pub use crate::be_sync::*;
 pub use crate::cb::*;
+pub use crate::config::*;
 pub use crate::conflict::*;
 pub use crate::db::*;
 pub use crate::deps::*;
@@ -44,9 +49,12 @@ pub use crate::list::*;
 pub use crate::mtree::*;
 pub use crate::package::*;
 pub use crate::remove::*;
+#[cfg(feature = "serde")]
+pub use crate::ser::*;
 pub use crate::signing::*;
 pub use crate::sync::*;
 pub use crate::trans::*;
 pub use crate::types::*;
 pub use crate::util::*;
 pub use crate::version::*;
+pub use crate::xfer::*;
@@ -1,20 +1,25 @@
+use crate::deps::dep_dup;
+use crate::utils::from_cstr;
 use crate::{
     free, Alpm, Backup, Conflict, Db, DbMut, Dep, DepMissing, Depend, DependMissing, FileConflict,
     Group, LoadedPackage, OwnedConflict, OwnedFileConflict, Package, Pkg,
 };
 
-use std::ffi::{c_void, CStr};
+use std::ffi::{c_void, CStr, CString};
 use std::fmt;
-use std::iter::{ExactSizeIterator, Iterator};
+use std::iter::{ExactSizeIterator, FusedIterator, Iterator};
 use std::marker::PhantomData;
 use std::mem::ManuallyDrop;
-use std::os::raw::c_char;
+use std::os::raw::{c_char, c_int};
 use std::ptr;
 
 use alpm_sys::*;
 
 extern "C" {
+    fn malloc(size: usize) -> *mut c_void;
+    fn strdup(cs: *const c_char) -> *mut c_char;
     fn strndup(cs: *const c_char, n: usize) -> *mut c_char;
+    fn strcmp(cs: *const c_char, ct: *const c_char) -> c_int;
 }
 
 pub unsafe trait IntoAlpmListItem<'a, 'b> {
@@ -23,6 +28,12 @@ pub unsafe trait IntoAlpmListItem<'a, 'b> {
     unsafe fn ptr_into_alpm_list_item(handle: &'a Alpm, ptr: *mut c_void) -> Self;
     #[doc(hidden)]
     unsafe fn ptr_as_alpm_list_item(handle: &'a Alpm, ptr: *mut c_void) -> Self::Borrow;
+    /// Copies an item so the copy can be owned by another list. Items that are
+    /// freed along with their list must be deep copied.
+    #[doc(hidden)]
+    unsafe fn dup_ptr(ptr: *mut c_void) -> *mut c_void {
+        ptr
+    }
 }
 
 pub unsafe trait AsAlpmListItemPtr<'a> {
@@ -54,6 +65,7 @@ where
     T: AsAlpmListItemPtr<'a>,
 {
     list: *mut alpm_list_t,
+    free: Option<unsafe extern "C" fn(_ptr: *mut c_void)>,
     _marker1: PhantomData<&'a T>,
     _marker2: PhantomData<D>,
 }
@@ -75,7 +87,7 @@ where
 {
     fn drop(&mut self) {
         if D::DROP {
-            if let Some(free) = T::FREE {
+            if let Some(free) = self.free {
                 unsafe { alpm_list_free_inner(self.list, Some(free)) }
             }
             unsafe { alpm_list_free(self.list) };
@@ -83,6 +95,15 @@ where
     }
 }
 
+/// Types that can be passed to functions taking a list, such as
+/// [`set_ignorepkgs`](Alpm::set_ignorepkgs).
+///
+/// This is implemented for [`AlpmList`], [`AlpmListMut`] and any iterator over items or
+/// references to items. An owned [`AlpmListMut<String>`] is handed over without copying.
+///
+/// Implementing this for vectors, slices and arrays directly would conflict with the
+/// iterator implementation, wrap them in a [`StringList`] instead. Owned strings passed
+/// that way are moved into the list rather than copied.
 pub trait IntoRawAlpmList<'a, T>
 where
     T: AsAlpmListItemPtr<'a>,
@@ -98,6 +119,7 @@ impl<'a> IntoRawAlpmList<'a, Pkg<'a>> for AlpmList<'a, LoadedPackage<'a>> {
     unsafe fn into_raw_alpm_list(self) -> RawAlpmList<'a, Pkg<'a>, Self::Drop> {
         RawAlpmList {
             list: self.list,
+            free: Pkg::FREE,
             _marker1: PhantomData,
             _marker2: PhantomData,
         }
@@ -109,6 +131,7 @@ impl<'a> IntoRawAlpmList<'a, Pkg<'a>> for AlpmList<'a, Package<'a>> {
     unsafe fn into_raw_alpm_list(self) -> RawAlpmList<'a, Pkg<'a>, Self::Drop> {
         RawAlpmList {
             list: self.list,
+            free: Pkg::FREE,
             _marker1: PhantomData,
             _marker2: PhantomData,
         }
@@ -123,6 +146,7 @@ where
     unsafe fn into_raw_alpm_list(self) -> RawAlpmList<'a, T, Self::Drop> {
         RawAlpmList {
             list: self.list,
+            free: T::FREE,
             _marker1: PhantomData,
             _marker2: PhantomData,
         }
@@ -137,6 +161,98 @@ where
     unsafe fn into_raw_alpm_list(self) -> RawAlpmList<'a, T, Self::Drop> {
         RawAlpmList {
             list: self.list.list,
+            free: T::FREE,
+            _marker1: PhantomData,
+            _marker2: PhantomData,
+        }
+    }
+}
+
+impl<'a> IntoRawAlpmList<'a, String> for AlpmListMut<'a, String> {
+    type Drop = True;
+    unsafe fn into_raw_alpm_list(self) -> RawAlpmList<'a, String, Self::Drop> {
+        let list = ManuallyDrop::new(self);
+        RawAlpmList {
+            list: list.list.list,
+            free: String::FREE,
+            _marker1: PhantomData,
+            _marker2: PhantomData,
+        }
+    }
+}
+
+/// Strings that can be put in a [`StringList`].
+///
+/// An owned `String` is turned into a C string in place, anything else is copied once.
+pub trait ListString {
+    #[doc(hidden)]
+    fn into_cstring(self) -> CString;
+}
+
+impl ListString for String {
+    fn into_cstring(self) -> CString {
+        cstring_lossy(self.into_bytes())
+    }
+}
+
+impl<S: AsRef<str> + ?Sized> ListString for &S {
+    fn into_cstring(self) -> CString {
+        let s = self.as_ref().as_bytes();
+        let mut bytes = Vec::with_capacity(s.len() + 1);
+        bytes.extend_from_slice(s);
+        cstring_lossy(bytes)
+    }
+}
+
+// Like copying with strndup, a string is cut off at its first nul byte.
+fn cstring_lossy(bytes: Vec<u8>) -> CString {
+    CString::new(bytes).unwrap_or_else(|e| {
+        let nul = e.nul_position();
+        let mut bytes = e.into_vec();
+        bytes.truncate(nul);
+        CString::new(bytes).unwrap()
+    })
+}
+
+/// Passes a collection of strings as a list, such as a `Vec<String>`, `Vec<&str>`, a slice
+/// or an array.
+///
+/// ```no_run
+/// use alpm::{Alpm, StringList};
+///
+/// let mut handle = Alpm::new("/", "tests/db").unwrap();
+/// let pkgs = vec!["linux".to_string(), "pacman".to_string()];
+/// handle.set_ignorepkgs(StringList(pkgs)).unwrap();
+/// handle.set_ignorepkgs(StringList(&["linux", "pacman"])).unwrap();
+/// ```
+///
+/// The strings are turned into C strings by Rust rather than copied with malloc, so owned
+/// strings are handed over without allocating.
+#[derive(Debug, Clone, Copy)]
+pub struct StringList<I>(pub I);
+
+unsafe extern "C" fn free_cstring(ptr: *mut c_void) {
+    drop(CString::from_raw(ptr as *mut c_char));
+}
+
+impl<'a, I> IntoRawAlpmList<'a, String> for StringList<I>
+where
+    I: IntoIterator,
+    I::Item: ListString,
+{
+    type Drop = True;
+    unsafe fn into_raw_alpm_list(self) -> RawAlpmList<'a, String, Self::Drop> {
+        let mut list = ptr::null_mut();
+
+        for s in self.0 {
+            list = alpm_list_add(list, s.into_cstring().into_raw() as *mut c_void);
+        }
+
+        // The strings were allocated by Rust so they are freed by Rust, libalpm copies the
+        // ones it keeps.
+        RawAlpmList {
+            list,
+            free: Some(free_cstring),
             _marker1: PhantomData,
             _marker2: PhantomData,
         }
@@ -172,6 +288,7 @@ where
 
         RawAlpmList {
             list,
+            free: T::FREE,
             _marker1: PhantomData,
             _marker2: PhantomData,
         }
@@ -255,6 +372,7 @@ impl<'a, 'b, T> AlpmList<'a, T>
 where
     T: IntoAlpmListItem<'a, 'b>,
 {
+    /// The number of items in the list. This walks the whole list.
     pub fn len(&self) -> usize {
         unsafe { alpm_list_count(self.list) }
     }
@@ -263,6 +381,22 @@ where
         self.list.is_null()
     }
 
+    /// The item at index `n`, or `None` if the list is shorter than that.
+    pub fn get(&'b self, n: usize) -> Option<T::Borrow> {
+        let mut item = self.list;
+        for _ in 0..n {
+            if item.is_null() {
+                break;
+            }
+            item = unsafe { alpm_list_next(item) };
+        }
+        if item.is_null() {
+            None
+        } else {
+            unsafe { Some(T::ptr_as_alpm_list_item(self.handle, (*item).data)) }
+        }
+    }
+
     pub fn first(&'b self) -> Option<T::Borrow> {
         if self.is_empty() {
             None
@@ -271,6 +405,7 @@ where
         }
     }
 
+    /// The last item in the list. The tail is reached in constant time.
     pub fn last(&'b self) -> Option<T::Borrow> {
         let item = unsafe { alpm_list_last(self.list) };
         if item.is_null() {
@@ -283,6 +418,35 @@ where
     pub fn iter(&'b self) -> Iter<'a, 'b, T> {
         self.into_iter()
     }
+
+    /// Returns the first item matching `f`.
+    pub fn find<F>(&'b self, mut f: F) -> Option<T::Borrow>
+    where
+        F: FnMut(&T::Borrow) -> bool,
+    {
+        self.iter().find(|item| f(item))
+    }
+}
+
+impl<'a> AlpmList<'a, String> {
+    /// Whether the list contains the string `s`.
+    pub fn contains_str(&self, s: &str) -> bool {
+        contains_str(self.list, s)
+    }
+}
+
+impl<'a, 's> AlpmList<'a, &'s str> {
+    /// Whether the list contains the string `s`.
+    pub fn contains_str(&self, s: &str) -> bool {
+        contains_str(self.list, s)
+    }
+}
+
+fn contains_str(list: *mut alpm_list_t, s: &str) -> bool {
+    match CString::new(s) {
+        Ok(s) => unsafe { !alpm_list_find_str(list, s.as_ptr()).is_null() },
+        Err(_) => false,
+    }
 }
 
 impl<'a> AlpmList<'a, String> {
@@ -295,13 +459,55 @@ impl<'a, T> AlpmList<'a, T>
 where
     for<'b> T: IntoAlpmListItem<'a, 'b>,
 {
+    /// Copies the list into one that can be modified and outlive the source.
+    ///
+    /// Packages, databases and other items borrowed from the handle are copied by
+    /// reference. Owned items such as strings and [`Depend`]s are deep copied.
     #[allow(clippy::wrong_self_convention)]
     pub fn to_list_mut(&self) -> AlpmListMut<'a, T> {
         let list = unsafe { alpm_list_copy(self.list) };
+        let mut curr = list;
+        while !curr.is_null() {
+            unsafe {
+                (*curr).data = T::dup_ptr((*curr).data);
+                curr = (*curr).next;
+            }
+        }
+
         AlpmListMut {
             list: AlpmList::from_parts(self.handle, list),
         }
     }
+
+    /// Copies the list into a `Vec`, see [`to_list_mut`](AlpmList::to_list_mut).
+    pub fn to_vec(&self) -> Vec<T> {
+        self.to_list_mut().into_iter().collect()
+    }
+}
+
+pub(crate) unsafe fn dup_str(s: *mut c_char) -> *mut c_char {
+    if s.is_null() {
+        s
+    } else {
+        strdup(s)
+    }
+}
+
+/// Copies `s` into a new nul terminated string allocated with malloc. `s` must not contain
+/// a nul byte.
+pub(crate) unsafe fn dup_bytes(s: &[u8]) -> *mut c_char {
+    let ptr = malloc(s.len() + 1) as *mut c_char;
+    assert!(!ptr.is_null(), "failed to allocate");
+    ptr::copy_nonoverlapping(s.as_ptr() as *const c_char, ptr, s.len());
+    ptr.add(s.len()).write(0);
+    ptr
+}
+
+pub(crate) unsafe fn dup_struct<T: Copy>(val: T) -> *mut T {
+    let ptr = malloc(std::mem::size_of::<T>()) as *mut T;
+    assert!(!ptr.is_null(), "failed to allocate");
+    ptr.write(val);
+    ptr
 }
 
 impl<'a, T> AlpmListMut<'a, T>
@@ -327,11 +533,60 @@ where
     }
 }
 
+impl<'a, T> AlpmListMut<'a, T>
+where
+    for<'b> T: IntoAlpmListItem<'a, 'b> + Push<'a>,
+{
+    /// Creates a list from `items`, keeping their order.
+    ///
+    /// This stands in for `FromIterator`, which can not be implemented as a list needs a
+    /// handle. Functions taking an [`IntoRawAlpmList`] also accept iterators directly.
+    pub fn from_items<I: IntoIterator<Item = T>>(handle: &'a Alpm, items: I) -> Self {
+        let mut list = AlpmListMut::new(handle);
+        list.extend(items);
+        list
+    }
+}
+
 impl<'a> AlpmListMut<'a, String> {
     pub fn push_str(&mut self, s: &str) {
         let s = unsafe { strndup(s.as_bytes().as_ptr() as _, s.len()) };
         unsafe { self.list.list = alpm_list_add(self.list.list, s as *mut c_void) };
     }
+
+    /// Removes the first string equal to `s`. Returns true if one was removed.
+    pub fn remove_str(&mut self, s: &str) -> bool {
+        let s = match CString::new(s) {
+            Ok(s) => s,
+            Err(_) => return false,
+        };
+        let mut data = ptr::null_mut();
+        unsafe { self.list.list = alpm_list_remove_str(self.list.list, s.as_ptr(), &mut data) };
+
+        if data.is_null() {
+            false
+        } else {
+            unsafe { free(data as _) };
+            true
+        }
+    }
+
+    /// Sorts the list by byte order.
+    pub fn sort(&mut self) {
+        unsafe { self.list.list = alpm_list_msort(self.list.list, self.len(), Some(str_cmp)) };
+    }
+}
+
+extern "C" fn str_cmp(a: *const c_void, b: *const c_void) -> c_int {
+    unsafe { strcmp(a as *const c_char, b as *const c_char) }
+}
+
+impl<'a, 's> Extend<&'s str> for AlpmListMut<'a, String> {
+    fn extend<I: IntoIterator<Item = &'s str>>(&mut self, iter: I) {
+        for s in iter {
+            self.push_str(s);
+        }
+    }
 }
 
 impl<'a, T> AlpmListMut<'a, T>
@@ -367,14 +622,47 @@ where
         }
 
         let item = unsafe { alpm_list_nth(self.list.list, n) };
-        unsafe { self.list.list = alpm_list_remove_item(self.list.list, item) };
-        let ret = unsafe {
-            Some(T::ptr_into_alpm_list_item(
-                self.handle,
-                (*self.list.list).data,
-            ))
-        };
-        unsafe { free(item as _) };
+        unsafe { Some(self.take_item(item)) }
+    }
+
+    /// Removes and returns the last item of the list.
+    pub fn pop(&mut self) -> Option<T> {
+        let item = unsafe { alpm_list_last(self.list.list) };
+        if item.is_null() {
+            None
+        } else {
+            unsafe { Some(self.take_item(item)) }
+        }
+    }
+
+    /// Removes and returns the first item matching `f`.
+    pub fn remove_first<F>(&mut self, mut f: F) -> Option<T>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let mut curr = self.list.list;
+
+        while !curr.is_null() {
+            let item =
+                unsafe { ManuallyDrop::new(T::ptr_into_alpm_list_item(self.handle, (*curr).data)) };
+            if f(&item) {
+                return unsafe { Some(self.take_item(curr)) };
+            }
+            curr = unsafe { (*curr).next };
+        }
+
+        None
+    }
+
+    /// Removes and drops every item in the list.
+    pub fn clear(&mut self) {
+        self.retain(|_| false);
+    }
+
+    unsafe fn take_item(&mut self, item: *mut alpm_list_t) -> T {
+        self.list.list = alpm_list_remove_item(self.list.list, item);
+        let ret = T::ptr_into_alpm_list_item(self.handle, (*item).data);
+        free(item as _);
         ret
     }
 
@@ -432,6 +720,8 @@ where
     fn into_iter(self) -> Self::IntoIter {
         Iter {
             current: self.list,
+            back: unsafe { alpm_list_last(self.list) },
+            remaining: self.len(),
             list: self,
         }
     }
@@ -447,6 +737,8 @@ where
     fn into_iter(self) -> Self::IntoIter {
         IntoIter {
             current: self.list,
+            back: unsafe { alpm_list_last(self.list) },
+            remaining: self.len(),
             list: self,
         }
     }
@@ -459,6 +751,8 @@ where
 {
     list: &'b AlpmList<'a, T>,
     current: *mut alpm_list_t,
+    back: *mut alpm_list_t,
+    remaining: usize,
 }
 
 impl<'a, 'b, T> fmt::Debug for Iter<'a, 'b, T>
@@ -477,6 +771,8 @@ where
 {
     list: AlpmList<'a, T>,
     current: *mut alpm_list_t,
+    back: *mut alpm_list_t,
+    remaining: usize,
 }
 
 impl<'a, T> fmt::Debug for IntoIter<'a, T>
@@ -493,15 +789,36 @@ where
     T: IntoAlpmListItem<'a, 'b>,
 {
     fn next_data(&mut self) -> Option<*mut c_void> {
-        if self.current.is_null() {
+        if self.remaining == 0 {
             None
         } else {
             let data = unsafe { (*(self.current)).data };
             self.current = unsafe { alpm_list_next(self.current) };
+            self.remaining -= 1;
+
+            Some(data)
+        }
+    }
+
+    fn next_back_data(&mut self) -> Option<*mut c_void> {
+        if self.remaining == 0 {
+            None
+        } else {
+            let data = unsafe { (*(self.back)).data };
+            self.back = unsafe { (*(self.back)).prev };
+            self.remaining -= 1;
 
             Some(data)
         }
     }
+
+    fn skip_data(&mut self, n: usize) {
+        let n = n.min(self.remaining);
+        for _ in 0..n {
+            self.current = unsafe { alpm_list_next(self.current) };
+        }
+        self.remaining -= n;
+    }
 }
 
 pub struct IntoIterMut<'a, T>
@@ -534,6 +851,10 @@ impl<'a, T> ExactSizeIterator for IntoIterMut<'a, T> where for<'b> T: IntoAlpmLi
 impl<'a, T> ExactSizeIterator for IntoIter<'a, T> where for<'b> T: IntoAlpmListItem<'a, 'b> {}
 impl<'a, 'b, T> ExactSizeIterator for Iter<'a, 'b, T> where T: IntoAlpmListItem<'a, 'b> {}
 
+impl<'a, T> FusedIterator for IntoIterMut<'a, T> where for<'b> T: IntoAlpmListItem<'a, 'b> {}
+impl<'a, T> FusedIterator for IntoIter<'a, T> where for<'b> T: IntoAlpmListItem<'a, 'b> {}
+impl<'a, 'b, T> FusedIterator for Iter<'a, 'b, T> where T: IntoAlpmListItem<'a, 'b> {}
+
 impl<'a, T> Iterator for IntoIterMut<'a, T>
 where
     for<'b> T: IntoAlpmListItem<'a, 'b>,
@@ -549,7 +870,7 @@ where
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let size = unsafe { alpm_list_count(self.list.list.list) };
+        let size = unsafe { alpm_list_count(self.current) };
         (size, Some(size))
     }
 }
@@ -569,8 +890,26 @@ where
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let size = unsafe { alpm_list_count(self.list.list) };
-        (size, Some(size))
+        (self.remaining, Some(self.remaining))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.skip_data(n);
+        self.next()
+    }
+}
+
+impl<'a, 'b, T> DoubleEndedIterator for Iter<'a, 'b, T>
+where
+    T: IntoAlpmListItem<'a, 'b>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let data = self.next_back_data();
+
+        match data {
+            Some(data) => unsafe { Some(T::ptr_as_alpm_list_item(self.list.handle, data)) },
+            None => None,
+        }
     }
 }
 
@@ -589,8 +928,26 @@ where
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let size = unsafe { alpm_list_count(self.list.list) };
-        (size, Some(size))
+        (self.remaining, Some(self.remaining))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.skip_data(n);
+        self.next()
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for IntoIter<'a, T>
+where
+    T: IntoAlpmListItem<'a, 'a>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let data = self.next_back_data();
+
+        match data {
+            Some(data) => unsafe { Some(T::ptr_as_alpm_list_item(self.list.handle, data)) },
+            None => None,
+        }
     }
 }
 
@@ -599,15 +956,36 @@ where
     T: IntoAlpmListItem<'a, 'a>,
 {
     fn next_data(&mut self) -> Option<*mut c_void> {
-        if self.current.is_null() {
+        if self.remaining == 0 {
             None
         } else {
             let data = unsafe { (*(self.current)).data };
             self.current = unsafe { alpm_list_next(self.current) };
+            self.remaining -= 1;
 
             Some(data)
         }
     }
+
+    fn next_back_data(&mut self) -> Option<*mut c_void> {
+        if self.remaining == 0 {
+            None
+        } else {
+            let data = unsafe { (*(self.back)).data };
+            self.back = unsafe { (*(self.back)).prev };
+            self.remaining -= 1;
+
+            Some(data)
+        }
+    }
+
+    fn skip_data(&mut self, n: usize) {
+        let n = n.min(self.remaining);
+        for _ in 0..n {
+            self.current = unsafe { alpm_list_next(self.current) };
+        }
+        self.remaining -= n;
+    }
 }
 
 impl<'a, T> IntoIterMut<'a, T>
@@ -679,14 +1057,20 @@ unsafe impl<'a> AsAlpmListItemPtr<'a> for Db<'a> {
 
 unsafe impl<'a> AsAlpmListItemPtr<'a> for Depend {
     type Output = Dep<'a>;
+    const FREE: Option<unsafe extern "C" fn(_ptr: *mut c_void)> = Some(free_dep);
 
     fn as_ptr(&self) -> *mut c_void {
-        self.inner as *mut c_void
+        unsafe { dep_dup(self.inner) as *mut c_void }
     }
 }
 
+unsafe extern "C" fn free_dep(ptr: *mut c_void) {
+    alpm_dep_free(ptr as *mut alpm_depend_t)
+}
+
 unsafe impl<'a, T: AsAlpmListItemPtr<'a>> AsAlpmListItemPtr<'a> for &T {
     type Output = T::Output;
+    const FREE: Option<unsafe extern "C" fn(_ptr: *mut c_void)> = T::FREE;
 
     fn as_ptr(&self) -> *mut c_void {
         (*self).as_ptr()
@@ -759,6 +1143,10 @@ unsafe impl<'a, 'b> IntoAlpmListItem<'a, 'b> for Depend {
     unsafe fn ptr_as_alpm_list_item(_handle: &'a Alpm, ptr: *mut c_void) -> Self::Borrow {
         Dep::from_ptr(ptr as *mut alpm_depend_t)
     }
+
+    unsafe fn dup_ptr(ptr: *mut c_void) -> *mut c_void {
+        dep_dup(ptr as *mut alpm_depend_t) as *mut c_void
+    }
 }
 
 unsafe impl<'a, 'b> IntoAlpmListItem<'a, 'b> for Dep<'a> {
@@ -803,6 +1191,14 @@ unsafe impl<'a, 'b> IntoAlpmListItem<'a, 'b> for OwnedFileConflict {
             phantom: PhantomData,
         }
     }
+
+    unsafe fn dup_ptr(ptr: *mut c_void) -> *mut c_void {
+        let mut conflict = *(ptr as *mut alpm_fileconflict_t);
+        conflict.target = dup_str(conflict.target);
+        conflict.file = dup_str(conflict.file);
+        conflict.ctarget = dup_str(conflict.ctarget);
+        dup_struct(conflict) as *mut c_void
+    }
 }
 
 unsafe impl<'a, 'b> IntoAlpmListItem<'a, 'b> for DependMissing {
@@ -822,6 +1218,14 @@ unsafe impl<'a, 'b> IntoAlpmListItem<'a, 'b> for DependMissing {
             phantom: PhantomData,
         }
     }
+
+    unsafe fn dup_ptr(ptr: *mut c_void) -> *mut c_void {
+        let mut miss = *(ptr as *mut alpm_depmissing_t);
+        miss.target = dup_str(miss.target);
+        miss.depend = dep_dup(miss.depend);
+        miss.causingpkg = dup_str(miss.causingpkg);
+        dup_struct(miss) as *mut c_void
+    }
 }
 
 unsafe impl<'a, 'b> IntoAlpmListItem<'a, 'b> for OwnedConflict {
@@ -832,6 +1236,14 @@ unsafe impl<'a, 'b> IntoAlpmListItem<'a, 'b> for OwnedConflict {
     unsafe fn ptr_as_alpm_list_item(_handle: &'a Alpm, ptr: *mut c_void) -> Self::Borrow {
         Conflict::from_ptr(ptr as *mut alpm_conflict_t)
     }
+
+    unsafe fn dup_ptr(ptr: *mut c_void) -> *mut c_void {
+        let mut conflict = *(ptr as *mut alpm_conflict_t);
+        conflict.package1 = dup_str(conflict.package1);
+        conflict.package2 = dup_str(conflict.package2);
+        conflict.reason = dep_dup(conflict.reason);
+        dup_struct(conflict) as *mut c_void
+    }
 }
 
 unsafe impl<'a, 'b> IntoAlpmListItem<'a, 'b> for Conflict<'a> {
@@ -883,12 +1295,10 @@ unsafe impl<'a, 'b> IntoAlpmListItem<'a, 'b> for DbMut<'a> {
 unsafe impl<'a, 'b> IntoAlpmListItem<'a, 'b> for &'a str {
     type Borrow = Self;
     unsafe fn ptr_into_alpm_list_item(_handle: &'a Alpm, ptr: *mut c_void) -> Self {
-        let s = CStr::from_ptr(ptr as *mut c_char);
-        s.to_str().unwrap()
+        from_cstr(ptr as *mut c_char)
     }
     unsafe fn ptr_as_alpm_list_item(_handle: &'a Alpm, ptr: *mut c_void) -> Self::Borrow {
-        let s = CStr::from_ptr(ptr as *mut c_char);
-        s.to_str().unwrap()
+        from_cstr(ptr as *mut c_char)
     }
 }
 
@@ -896,13 +1306,16 @@ unsafe impl<'a, 'b> IntoAlpmListItem<'a, 'b> for String {
     type Borrow = &'b str;
     unsafe fn ptr_into_alpm_list_item(_handle: &'a Alpm, ptr: *mut c_void) -> Self {
         let s = CStr::from_ptr(ptr as *mut c_char);
-        let s = s.to_str().unwrap().to_string();
+        let s = s.to_string_lossy().into_owned();
         free(ptr);
         s
     }
     unsafe fn ptr_as_alpm_list_item(_handle: &'a Alpm, ptr: *mut c_void) -> Self::Borrow {
-        let s = CStr::from_ptr(ptr as *mut c_char);
-        s.to_str().unwrap()
+        from_cstr(ptr as *mut c_char)
+    }
+
+    unsafe fn dup_ptr(ptr: *mut c_void) -> *mut c_void {
+        dup_str(ptr as *mut c_char) as *mut c_void
     }
 }
 
@@ -911,6 +1324,241 @@ mod tests {
     use super::*;
     use crate::SigLevel;
 
+    #[test]
+    fn test_from_items() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+
+        let items = ["a", "b", "c"].iter().map(|s| s.to_string());
+        let mut list = AlpmListMut::from_items(&handle, items);
+        list.extend(["d", "e"].iter().copied());
+        list.extend(vec!["f".to_string()]);
+
+        let collected = list.iter().collect::<Vec<_>>();
+        assert_eq!(collected, ["a", "b", "c", "d", "e", "f"]);
+    }
+
+    #[test]
+    fn test_iter_rev() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let db = handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        let pkgs = db.pkgs();
+
+        let forward = pkgs.iter().map(|p| p.name()).collect::<Vec<_>>();
+        let mut backward = pkgs.iter().rev().map(|p| p.name()).collect::<Vec<_>>();
+        backward.reverse();
+        assert_eq!(forward, backward);
+
+        let owned = pkgs.into_iter().rev().map(|p| p.name()).collect::<Vec<_>>();
+        assert_eq!(owned.first(), forward.last());
+
+        let mut iter = pkgs.iter();
+        let mut count = 0;
+        while iter.next().is_some() {
+            count += 1;
+            assert_eq!(iter.len(), forward.len() - count);
+            if iter.next_back().is_some() {
+                count += 1;
+            }
+        }
+        assert_eq!(count, forward.len());
+        assert!(iter.next_back().is_none());
+
+        let empty = AlpmListMut::<String>::new(&handle);
+        assert!(empty.iter().next_back().is_none());
+    }
+
+    #[test]
+    fn test_iter_nth() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let db = handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        let pkgs = db.pkgs();
+        let names = pkgs.iter().map(|p| p.name()).collect::<Vec<_>>();
+
+        assert_eq!(pkgs.len(), names.len());
+        assert_eq!(pkgs.get(3).unwrap().name(), names[3]);
+        assert_eq!(pkgs.last().unwrap().name(), *names.last().unwrap());
+        assert!(pkgs.get(names.len()).is_none());
+
+        let mut iter = pkgs.iter();
+        assert_eq!(iter.nth(2).unwrap().name(), names[2]);
+        assert_eq!(iter.len(), names.len() - 3);
+        assert_eq!(iter.nth(1).unwrap().name(), names[4]);
+        assert!(iter.nth(names.len()).is_none());
+        assert_eq!(iter.size_hint(), (0, Some(0)));
+        assert!(iter.next().is_none());
+        assert!(iter.next_back().is_none());
+
+        let mut iter = pkgs.into_iter();
+        assert_eq!(iter.nth(1).unwrap().name(), names[1]);
+        assert_eq!(iter.next_back().unwrap().name(), *names.last().unwrap());
+        assert_eq!(iter.len(), names.len() - 3);
+
+        let mut count = 0;
+        for pkg in &pkgs {
+            assert_eq!(pkg.name(), names[count]);
+            count += 1;
+        }
+        assert_eq!(count, names.len());
+
+        let linux = db.pkg("linux").unwrap();
+        let depends = linux.depends();
+        assert_eq!(depends.len(), 4);
+        assert_eq!(depends.get(1).unwrap().name(), "linux-firmware");
+        assert_eq!(depends.last().unwrap().name(), "mkinitcpio");
+        assert_eq!(depends.iter().nth(2).unwrap().name(), "kmod");
+        let rev = (&depends)
+            .into_iter()
+            .rev()
+            .map(|d| d.name())
+            .collect::<Vec<_>>();
+        assert_eq!(rev, ["mkinitcpio", "kmod", "linux-firmware", "coreutils"]);
+
+        let mut owned = depends.to_list_mut().into_iter();
+        assert_eq!(owned.size_hint(), (4, Some(4)));
+        owned.next();
+        assert_eq!(owned.len(), 3);
+        assert_eq!(owned.nth(1).unwrap().name(), "kmod");
+        assert_eq!(owned.len(), 1);
+
+        let empty = AlpmListMut::<String>::new(&handle);
+        assert!(empty.get(0).is_none());
+        assert!(empty.last().is_none());
+        assert!(empty.iter().nth(1).is_none());
+    }
+
+    #[test]
+    fn test_list_mut_mutation() {
+        let mut handle = Alpm::new("/", "tests/db").unwrap();
+
+        let mut strings = AlpmListMut::new(&handle);
+        strings.push("a".to_string());
+        strings.extend(["b", "c", "d"].iter().copied());
+        assert_eq!(strings.pop().unwrap(), "d");
+        assert_eq!(strings.remove_first(|s| s == "b").unwrap(), "b");
+        assert!(strings.remove_first(|s| s == "b").is_none());
+        assert_eq!(strings.iter().collect::<Vec<_>>(), ["a", "c"]);
+        strings.clear();
+        assert!(strings.is_empty());
+        assert!(strings.pop().is_none());
+        drop(strings);
+
+        let mut deps = AlpmListMut::new(&handle);
+        deps.extend(["foo", "bar>=1", "baz"].iter().map(|d| Depend::new(*d)));
+        let bar = deps.remove_first(|d| d.name() == "bar").unwrap();
+        assert_eq!(bar.to_string(), "bar>=1");
+        assert_eq!(deps.pop().unwrap().name(), "baz");
+        deps.push(Depend::new("qux"));
+        let names = deps.iter().map(|d| d.name()).collect::<Vec<_>>();
+        assert_eq!(names, ["foo", "qux"]);
+        drop(deps);
+
+        let db = handle.localdb();
+        let mut pkgs = AlpmListMut::new(&handle);
+        pkgs.extend(db.pkgs().iter().take(3));
+        let last = pkgs.pop().unwrap();
+        assert_eq!(last.name(), db.pkgs().get(2).unwrap().name());
+        assert_eq!(pkgs.len(), 2);
+        drop(pkgs);
+
+        let other = Alpm::new("/", "tests/db").unwrap();
+        let mut ignore = AlpmListMut::from_items(&other, vec!["x".to_string(), "y".to_string()]);
+        ignore.push_str("z");
+        ignore.remove_first(|s| s == "y");
+        handle.set_ignorepkgs(&ignore).unwrap();
+        drop(ignore);
+        assert_eq!(handle.ignorepkgs().iter().collect::<Vec<_>>(), ["x", "z"]);
+    }
+
+    #[test]
+    fn test_into_raw_list_sources() {
+        let mut handle = Alpm::new("/", "tests/db").unwrap();
+        let names = (0..5000).map(|i| format!("pkg{}", i)).collect::<Vec<_>>();
+
+        handle.set_ignorepkgs(names.iter()).unwrap();
+        assert_eq!(handle.ignorepkgs().len(), names.len());
+        assert_eq!(handle.ignorepkgs().last().unwrap(), "pkg4999");
+
+        let slice: &[String] = &names[..3];
+        handle.set_ignorepkgs(slice.iter()).unwrap();
+        assert_eq!(handle.ignorepkgs().iter().collect::<Vec<_>>(), slice);
+
+        handle.set_ignorepkgs(["a", "b"].iter()).unwrap();
+        assert_eq!(handle.ignorepkgs().iter().collect::<Vec<_>>(), ["a", "b"]);
+
+        handle.set_ignorepkgs(names.clone().into_iter()).unwrap();
+        assert_eq!(handle.ignorepkgs().len(), names.len());
+
+        let other = Alpm::new("/", "tests/db").unwrap();
+        let list = AlpmListMut::from_items(&other, names.iter().take(10).cloned());
+        handle.set_ignorepkgs(&list).unwrap();
+        assert_eq!(handle.ignorepkgs().len(), 10);
+        handle.set_ignorepkgs(list).unwrap();
+        assert_eq!(handle.ignorepkgs().len(), 10);
+        assert_eq!(handle.ignorepkgs().first().unwrap(), "pkg0");
+
+        handle.set_ignorepkgs(StringList(&names)).unwrap();
+        assert_eq!(handle.ignorepkgs().len(), names.len());
+        handle.set_ignorepkgs(StringList(slice)).unwrap();
+        assert_eq!(handle.ignorepkgs().iter().collect::<Vec<_>>(), slice);
+        handle.set_ignorepkgs(StringList(["a", "b\0c"])).unwrap();
+        assert_eq!(handle.ignorepkgs().iter().collect::<Vec<_>>(), ["a", "b"]);
+        handle.set_ignorepkgs(StringList(vec!["c", "d"])).unwrap();
+        assert_eq!(handle.ignorepkgs().iter().collect::<Vec<_>>(), ["c", "d"]);
+        handle.set_ignorepkgs(StringList(names)).unwrap();
+        assert_eq!(handle.ignorepkgs().last().unwrap(), "pkg4999");
+    }
+
+    #[test]
+    fn test_find_contains() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let linux = handle.localdb().pkg("linux").unwrap();
+
+        let depends = linux.depends();
+        let kmod = depends.find(|d| d.name() == "kmod").unwrap();
+        assert_eq!(kmod.to_string(), "kmod");
+        assert!(depends.find(|d| d.name() == "bash").is_none());
+
+        let pkgs = handle.localdb().pkgs();
+        let bash = pkgs.find(|p| p.provides().iter().any(|d| d.name() == "sh"));
+        assert_eq!(bash.unwrap().name(), "bash");
+
+        let groups = linux.groups();
+        assert!(groups.contains_str("base"));
+        assert!(!groups.contains_str("base-devel"));
+        assert!(!groups.contains_str("ba\0se"));
+        assert_eq!(groups.find(|g| g.starts_with("ba")), Some("base"));
+
+        let mut owned = AlpmListMut::new(&handle);
+        owned.push("a".to_string());
+        owned.push("b".to_string());
+        assert!(owned.contains_str("b"));
+        assert!(!owned.contains_str("c"));
+        assert!(!AlpmListMut::<String>::new(&handle).contains_str("a"));
+        assert_eq!(owned.find(|s| *s != "a").unwrap(), "b");
+    }
+
+    #[test]
+    fn test_sort_remove() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let mut list = AlpmListMut::new(&handle);
+        list.push("c".to_string());
+        list.push("a".to_string());
+        list.push("b".to_string());
+        assert_eq!(list.len(), 3);
+
+        list.sort();
+        assert_eq!(list.iter().collect::<Vec<_>>(), ["a", "b", "c"]);
+
+        assert!(list.remove_str("b"));
+        assert!(!list.remove_str("b"));
+        assert_eq!(list.iter().collect::<Vec<_>>(), ["a", "c"]);
+
+        assert_eq!(list.remove(1).unwrap(), "c");
+        assert_eq!(list.iter().collect::<Vec<_>>(), ["a"]);
+        assert!(list.remove_str("a"));
+        assert!(list.is_empty());
+    }
+
     #[test]
     fn test_depends_list_debug() {
         let handle = Alpm::new("/", "tests/db").unwrap();
@@ -930,6 +1578,38 @@ mod tests {
         assert_eq!(depends.first().unwrap().to_string(), "coreutils");
     }
 
+    #[test]
+    fn test_to_vec() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let pkg = handle.localdb().pkg("linux").unwrap();
+        let depends = pkg.depends();
+
+        let names = depends
+            .to_vec()
+            .iter()
+            .map(|d| d.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["coreutils", "linux-firmware", "kmod", "mkinitcpio"]);
+
+        let mut list = depends.to_list_mut();
+        assert_eq!(list.len(), 4);
+        list.remove(0);
+        assert_eq!(list.len(), 3);
+        assert_eq!(depends.len(), 4);
+
+        let owned = AlpmListMut::from_items(&handle, depends.iter().map(|d| d.to_depend()));
+        let copy = owned.to_list_mut();
+        drop(owned);
+        let copy = copy.to_vec();
+        assert_eq!(copy.len(), 4);
+        assert_eq!(copy[3].name(), "mkinitcpio");
+
+        let strings = AlpmListMut::from_items(&handle, vec!["a".to_string(), "b".to_string()]);
+        let copy = strings.to_list_mut();
+        drop(strings);
+        assert_eq!(copy.to_vec(), ["a", "b"]);
+    }
+
     #[test]
     fn test_is_empty() {
         let handle = Alpm::new("/", "tests/db").unwrap();
@@ -1009,14 +1689,14 @@ mod tests {
         pkg.sync_new_version(handle.syncdbs());
         pkg.sync_new_version(&handle.syncdbs().to_list_mut().remove_list(0));
         pkg.sync_new_version(vec![db].into_iter());
-        pkg.sync_new_version(vec![db].iter());
+        pkg.sync_new_version([db].iter());
     }
 
     #[test]
     fn test_into_raw_alpm_list2() {
         let mut handle = Alpm::new("/", "tests/db").unwrap();
 
-        let list = vec![Depend::new("foo")];
+        let list = [Depend::new("foo")];
         handle.set_assume_installed(list.iter()).unwrap();
     }
 }
@@ -1,11 +1,17 @@
-use crate::Pkg;
+use crate::{BackupStatus, Error, Pkg, Result};
 use alpm_sys::*;
 
 use libarchive::archive::Handle;
 use libarchive::reader::ReaderEntry;
 use libarchive3_sys::ffi::*;
 
-use std::{fmt, ptr};
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::{fmt, fs, io, ptr};
+
+const S_IFMT: u32 = 0o170000;
 
 pub struct MTree<'a> {
     pub(crate) pkg: &'a Pkg<'a>,
@@ -30,6 +36,109 @@ impl<'a> Drop for MTree<'a> {
     }
 }
 
+/// A file listed in a package's mtree.
+///
+/// The values are copied out of the archive so entries stay valid after the mtree moves on.
+/// libarchive does not expose the md5 and sha256 digests recorded in the mtree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MTreeEntry {
+    path: String,
+    size: i64,
+    mode: u32,
+    uid: i64,
+    gid: i64,
+    mtime: i64,
+    link_target: Option<String>,
+}
+
+impl MTreeEntry {
+    unsafe fn new(entry: *mut Struct_archive_entry) -> MTreeEntry {
+        MTreeEntry {
+            path: string(archive_entry_pathname(entry))
+                .map(|path| match path.strip_prefix("./") {
+                    Some(path) => path.to_string(),
+                    None => path,
+                })
+                .unwrap_or_default(),
+            size: archive_entry_size(entry),
+            mode: archive_entry_mode(entry) as u32,
+            uid: archive_entry_uid(entry),
+            gid: archive_entry_gid(entry),
+            mtime: archive_entry_mtime(entry) as i64,
+            link_target: string(archive_entry_symlink(entry)),
+        }
+    }
+
+    /// The path of the file, relative to the root without the leading `./`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn size(&self) -> i64 {
+        self.size
+    }
+
+    /// The file type and permission bits.
+    pub fn mode(&self) -> u32 {
+        self.mode
+    }
+
+    pub fn uid(&self) -> i64 {
+        self.uid
+    }
+
+    pub fn gid(&self) -> i64 {
+        self.gid
+    }
+
+    pub fn mtime(&self) -> i64 {
+        self.mtime
+    }
+
+    /// The target of a symlink.
+    pub fn link_target(&self) -> Option<&str> {
+        self.link_target.as_deref()
+    }
+}
+
+unsafe fn string(s: *const c_char) -> Option<String> {
+    s.as_ref()
+        .map(|s| CStr::from_ptr(s).to_string_lossy().into_owned())
+}
+
+/// Iterator over the entries of an [`MTree`].
+#[derive(Debug)]
+pub struct MTreeEntries<'m, 'a> {
+    mtree: &'m mut MTree<'a>,
+}
+
+impl<'m, 'a> Iterator for MTreeEntries<'m, 'a> {
+    type Item = Result<MTreeEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut entry = ptr::null_mut();
+        let ret =
+            unsafe { alpm_pkg_mtree_next(self.mtree.pkg.pkg, self.mtree.archive, &mut entry) };
+
+        match ret {
+            ARCHIVE_OK | ARCHIVE_WARN => unsafe {
+                Some(Ok(MTreeEntry::new(entry as *mut Struct_archive_entry)))
+            },
+            ARCHIVE_EOF => None,
+            _ => Some(Err(Error::Libarchive)),
+        }
+    }
+}
+
+impl<'a> MTree<'a> {
+    /// Reads the remaining entries.
+    ///
+    /// An entry that can not be read is returned as [`Error::Libarchive`].
+    pub fn entries(&mut self) -> MTreeEntries<'_, 'a> {
+        MTreeEntries { mtree: self }
+    }
+}
+
 impl<'a> Iterator for MTree<'a> {
     type Item = ReaderEntry;
 
@@ -45,10 +154,158 @@ impl<'a> Iterator for MTree<'a> {
     }
 }
 
+/// A difference between a package's mtree and the filesystem, see [`Pkg::verify_files`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileIssue {
+    /// The path of the file, relative to the root.
+    pub path: String,
+    pub kind: FileIssueKind,
+}
+
+impl FileIssue {
+    /// Whether the issue is an error. Modified backup files are expected and are not.
+    pub fn is_error(&self) -> bool {
+        self.kind != FileIssueKind::BackupModified
+    }
+}
+
+/// The kind of a [`FileIssue`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileIssueKind {
+    Missing,
+    /// The file exists but its metadata could not be read.
+    Unreadable,
+    /// The file type differs, e.g. a directory where a file is expected.
+    Type,
+    Size {
+        expected: i64,
+        actual: i64,
+    },
+    /// The permission bits differ.
+    Mode {
+        expected: u32,
+        actual: u32,
+    },
+    Uid {
+        expected: i64,
+        actual: i64,
+    },
+    Gid {
+        expected: i64,
+        actual: i64,
+    },
+    LinkTarget {
+        expected: String,
+        actual: String,
+    },
+    /// A backup file differs from the hash recorded when it was installed.
+    BackupModified,
+}
+
+impl<'a> Pkg<'a> {
+    /// Compares the files in the package's mtree against the filesystem under `root`, like
+    /// `pacman -Qkk`.
+    ///
+    /// Paths matching NoExtract are skipped, as are the package metadata files such as
+    /// `.PKGINFO`. Backup files are not size checked, instead a modified backup is
+    /// reported as [`FileIssueKind::BackupModified`]. Checksums are not compared as
+    /// libarchive does not expose the digests recorded in the mtree.
+    pub fn verify_files(&self, root: &str) -> Result<Vec<FileIssue>> {
+        let root = Path::new(root);
+        let backup = self.backup();
+        let mut mtree = self.mtree()?;
+        let mut issues = Vec::new();
+
+        for entry in mtree.entries() {
+            let entry = entry?;
+            if entry.path().starts_with('.') || self.is_noextract(entry.path()) {
+                continue;
+            }
+
+            let mut issue = |kind| {
+                issues.push(FileIssue {
+                    path: entry.path().to_string(),
+                    kind,
+                })
+            };
+            let path = root.join(entry.path());
+            let meta = match fs::symlink_metadata(&path) {
+                Ok(meta) => meta,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    issue(FileIssueKind::Missing);
+                    continue;
+                }
+                Err(_) => {
+                    issue(FileIssueKind::Unreadable);
+                    continue;
+                }
+            };
+
+            if meta.mode() & S_IFMT != entry.mode() & S_IFMT {
+                issue(FileIssueKind::Type);
+                continue;
+            }
+            if meta.mode() & 0o7777 != entry.mode() & 0o7777 {
+                issue(FileIssueKind::Mode {
+                    expected: entry.mode() & 0o7777,
+                    actual: meta.mode() & 0o7777,
+                });
+            }
+            if i64::from(meta.uid()) != entry.uid() {
+                issue(FileIssueKind::Uid {
+                    expected: entry.uid(),
+                    actual: meta.uid().into(),
+                });
+            }
+            if i64::from(meta.gid()) != entry.gid() {
+                issue(FileIssueKind::Gid {
+                    expected: entry.gid(),
+                    actual: meta.gid().into(),
+                });
+            }
+
+            if let Some(expected) = entry.link_target() {
+                let actual = fs::read_link(&path).unwrap_or_default();
+                if actual != Path::new(expected) {
+                    issue(FileIssueKind::LinkTarget {
+                        expected: expected.to_string(),
+                        actual: actual.to_string_lossy().into_owned(),
+                    });
+                }
+            } else if meta.is_file() {
+                match backup.iter().find(|b| b.name() == entry.path()) {
+                    Some(b) if b.status(root) == BackupStatus::Modified => {
+                        issue(FileIssueKind::BackupModified)
+                    }
+                    Some(_) => (),
+                    None if meta.len() as i64 != entry.size() => issue(FileIssueKind::Size {
+                        expected: entry.size(),
+                        actual: meta.len() as i64,
+                    }),
+                    None => (),
+                }
+            }
+        }
+
+        Ok(issues)
+    }
+
+    fn is_noextract(&self, path: &str) -> bool {
+        let path = match CString::new(path) {
+            Ok(path) => path,
+            Err(_) => return false,
+        };
+        unsafe { alpm_option_match_noextract(self.handle.handle, path.as_ptr()) == 0 }
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use crate::utils::TempDir;
     use crate::Alpm;
     use libarchive::archive::Entry;
+    use std::os::unix::fs::PermissionsExt;
 
     #[test]
     fn test_mtree() {
@@ -63,4 +320,75 @@ mod tests {
         assert!(file.size() == 4900);
         assert!(mtree.count() > 10);
     }
+
+    #[test]
+    fn test_mtree_entries() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let pkg = handle.localdb().pkg("vifm").unwrap();
+        let mut mtree = pkg.mtree().unwrap();
+        let entries = mtree
+            .entries()
+            .take(6)
+            .collect::<crate::Result<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(entries[0].path(), ".BUILDINFO");
+        assert_eq!(entries[0].size(), 4900);
+        assert_eq!(entries[0].mode(), 0o100644);
+        assert_eq!(entries[0].uid(), 0);
+        assert_eq!(entries[0].gid(), 0);
+        assert_eq!(entries[0].mtime(), 1542052992);
+        assert_eq!(entries[0].link_target(), None);
+
+        assert_eq!(entries[3].path(), "usr");
+        assert_eq!(entries[3].mode(), 0o040755);
+
+        assert_eq!(entries[5].path(), "usr/bin/vifm");
+        assert_eq!(entries[5].size(), 1047088);
+        assert_eq!(entries[5].mode(), 0o100755);
+
+        let next = mtree.entries().next().unwrap().unwrap();
+        assert_eq!(next.path(), "usr/bin/vifm-convert-dircolors");
+        assert!(mtree.entries().all(|e| e.is_ok()));
+        assert!(mtree.entries().next().is_none());
+    }
+
+    #[test]
+    fn test_verify_files() {
+        let root = TempDir::new("verify-files");
+        let bin = root.join("usr").join("bin");
+        fs::create_dir_all(&bin).unwrap();
+        let vifm = fs::File::create(bin.join("vifm")).unwrap();
+        vifm.set_len(1047088).unwrap();
+        fs::write(bin.join("vifm-convert-dircolors"), "edited\n").unwrap();
+        for file in &["vifm", "vifm-convert-dircolors"] {
+            fs::set_permissions(bin.join(file), fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        let mut handle = Alpm::new("/", "tests/db").unwrap();
+        handle.add_noextract("usr/share/*").unwrap();
+        let pkg = handle.localdb().pkg("vifm").unwrap();
+        let issues = pkg.verify_files(root.to_str().unwrap()).unwrap();
+        let kinds = |path: &str| {
+            issues
+                .iter()
+                .filter(|i| i.path == path)
+                .map(|i| i.kind.clone())
+                .filter(|k| !matches!(k, FileIssueKind::Uid { .. } | FileIssueKind::Gid { .. }))
+                .collect::<Vec<_>>()
+        };
+
+        assert!(kinds("usr/bin/vifm").is_empty());
+        assert_eq!(
+            kinds("usr/bin/vifm-convert-dircolors"),
+            [FileIssueKind::Size {
+                expected: 9952,
+                actual: 7
+            }]
+        );
+        assert_eq!(kinds("usr/bin/vifm-pause"), [FileIssueKind::Missing]);
+        assert!(issues.iter().all(|i| i.is_error()));
+        assert!(issues.iter().all(|i| !i.path.starts_with('.')));
+        assert!(issues.iter().all(|i| !i.path.starts_with("usr/share/")));
+    }
 }
@@ -1,12 +1,16 @@
 use crate::utils::*;
 use crate::{
-    Alpm, AlpmList, AlpmListMut, Backup, ChangeLog, Db, Dep, FileList, PackageFrom, PackageReason,
-    PackageValidation, Result, Signature, Ver,
+    Alpm, AlpmList, AlpmListMut, AsDep, Backup, BackupStatus, ChangeLog, Db, Dep, FileList,
+    IntoRawAlpmList, PackageFrom, PackageReason, PackageValidation, Result, SatisfierCache,
+    Signature, Ver,
 };
 
 #[cfg(feature = "mtree")]
 use crate::MTree;
 
+use std::ffi::CString;
+use std::hash::{Hash, Hasher};
+use std::iter::once;
 use std::mem::transmute;
 use std::ops::Deref;
 use std::{fmt, ptr};
@@ -40,6 +44,36 @@ pub struct Pkg<'a> {
     pub(crate) pkg: *mut alpm_pkg_t,
 }
 
+/// The packages depending on a package, see [`Pkg::reverse_deps`].
+#[derive(Debug)]
+pub struct ReverseDeps<'a> {
+    required_by: AlpmListMut<'a, String>,
+    optional_for: AlpmListMut<'a, String>,
+}
+
+impl<'a> ReverseDeps<'a> {
+    pub fn required_by(&self) -> AlpmList<'a, String> {
+        self.required_by.as_list()
+    }
+
+    pub fn optional_for(&self) -> AlpmList<'a, String> {
+        self.optional_for.as_list()
+    }
+
+    pub fn required_by_count(&self) -> usize {
+        self.required_by.len()
+    }
+
+    pub fn optional_for_count(&self) -> usize {
+        self.optional_for.len()
+    }
+
+    /// Whether no package depends on the package, optionally or not.
+    pub fn is_empty(&self) -> bool {
+        self.required_by.is_empty() && self.optional_for.is_empty()
+    }
+}
+
 impl<'a> fmt::Debug for Pkg<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Pkg")
@@ -58,6 +92,49 @@ impl<'a> fmt::Debug for Package<'a> {
     }
 }
 
+impl<'a> fmt::Display for Pkg<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.name(), self.version())
+    }
+}
+
+impl<'a> fmt::Display for Package<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.pkg, f)
+    }
+}
+
+/// Packages are compared by name, version and the database they come from.
+///
+/// Packages loaded from files have no database and are compared by file name instead.
+impl<'a> PartialEq for Pkg<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.pkg == other.pkg || self.identity() == other.identity()
+    }
+}
+
+impl<'a> Eq for Pkg<'a> {}
+
+impl<'a> Hash for Pkg<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.identity().hash(state)
+    }
+}
+
+impl<'a> PartialEq for Package<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.pkg == other.pkg
+    }
+}
+
+impl<'a> Eq for Package<'a> {}
+
+impl<'a> Hash for Package<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.pkg.hash(state)
+    }
+}
+
 impl<'a> Deref for Package<'a> {
     type Target = Pkg<'a>;
     fn deref(&self) -> &Self::Target {
@@ -114,6 +191,12 @@ impl<'a> Pkg<'a> {
         unsafe { from_cstr_optional(desc) }
     }
 
+    /// The raw bytes of the description, which need not be UTF-8.
+    pub fn desc_bytes(&self) -> Option<&'a [u8]> {
+        let desc = unsafe { alpm_pkg_get_desc(self.pkg) };
+        unsafe { from_cstr_bytes_optional(desc) }
+    }
+
     pub fn url(&self) -> Option<&'a str> {
         let url = unsafe { alpm_pkg_get_url(self.pkg) };
         unsafe { from_cstr_optional(url) }
@@ -138,6 +221,12 @@ impl<'a> Pkg<'a> {
         unsafe { from_cstr_optional(packager) }
     }
 
+    /// The raw bytes of the packager, which need not be UTF-8.
+    pub fn packager_bytes(&self) -> Option<&'a [u8]> {
+        let packager = unsafe { alpm_pkg_get_packager(self.pkg) };
+        unsafe { from_cstr_bytes_optional(packager) }
+    }
+
     pub fn md5sum(&self) -> Option<&'a str> {
         let md5sum = unsafe { alpm_pkg_get_md5sum(self.pkg) };
         unsafe { from_cstr_optional(md5sum) }
@@ -170,7 +259,7 @@ impl<'a> Pkg<'a> {
 
     pub fn validation(&self) -> PackageValidation {
         let validation = unsafe { alpm_pkg_get_validation(self.pkg) };
-        PackageValidation::from_bits(validation as u32).unwrap()
+        unsafe { PackageValidation::from_bits_unchecked(validation as u32) }
     }
 
     pub fn licenses(&self) -> AlpmList<'a, &'a str> {
@@ -218,16 +307,35 @@ impl<'a> Pkg<'a> {
         AlpmList::from_parts(self.handle, list)
     }
 
-    pub fn files(&self) -> FileList {
-        let files = unsafe { *alpm_pkg_get_files(self.pkg) };
-        FileList { inner: files }
+    /// The files owned by this package.
+    ///
+    /// This is empty if the file list was not loaded, see [`try_files`](Pkg::try_files).
+    pub fn files(&self) -> FileList<'a> {
+        unsafe { FileList::from_ptr(alpm_pkg_get_files(self.pkg)) }
     }
 
+    /// The backup entries of this package.
+    ///
+    /// This is empty for sync packages as sync databases do not record backup entries, see
+    /// [`try_backup`](Pkg::try_backup).
     pub fn backup(&self) -> AlpmList<'a, Backup> {
         let list = unsafe { alpm_pkg_get_backup(self.pkg) };
         AlpmList::from_parts(self.handle, list)
     }
 
+    fn identity(&self) -> (&'a str, &'a [u8], PackageFrom, Option<&'a str>) {
+        let source = match self.db() {
+            Some(db) => Some(db.name()),
+            None => Some(self.filename()),
+        };
+        (
+            self.name(),
+            self.version().as_bytes(),
+            self.origin(),
+            source,
+        )
+    }
+
     pub fn db(&self) -> Option<Db<'a>> {
         let db = unsafe { alpm_pkg_get_db(self.pkg) };
         self.handle.check_null(db).ok()?;
@@ -237,6 +345,10 @@ impl<'a> Pkg<'a> {
         })
     }
 
+    /// Opens the changelog of this package.
+    ///
+    /// Fails for sync packages as changelogs are only available for installed packages
+    /// and package files.
     pub fn changelog(&self) -> Result<ChangeLog> {
         let changelog = unsafe { alpm_pkg_changelog_open(self.pkg) };
         self.handle.check_null(changelog)?;
@@ -244,6 +356,8 @@ impl<'a> Pkg<'a> {
         let changelog = ChangeLog {
             pkg: self,
             stream: changelog,
+            buf: Vec::new(),
+            pos: 0,
         };
 
         Ok(changelog)
@@ -269,15 +383,174 @@ impl<'a> Pkg<'a> {
         AlpmListMut::from_parts(self.handle, list)
     }
 
+    /// The number of packages that depend on this package.
+    ///
+    /// This computes [`required_by`](Pkg::required_by), use [`reverse_deps`](Pkg::reverse_deps)
+    /// when the names are needed as well.
+    pub fn required_by_count(&self) -> usize {
+        self.required_by().len()
+    }
+
+    /// The number of packages that optionally depend on this package.
+    pub fn optional_for_count(&self) -> usize {
+        self.optional_for().len()
+    }
+
+    /// Computes [`required_by`](Pkg::required_by) and [`optional_for`](Pkg::optional_for)
+    /// once so they can be read repeatedly without walking the database again.
+    ///
+    /// With a `cache` the dependencies are checked in Rust, reusing the results of earlier
+    /// checks, instead of by libalpm. The databases searched and the order of the result
+    /// are the same either way.
+    pub fn reverse_deps(&self, cache: Option<&mut SatisfierCache>) -> ReverseDeps<'a> {
+        let cache = match cache {
+            Some(cache) => cache,
+            None => {
+                return ReverseDeps {
+                    required_by: self.required_by(),
+                    optional_for: self.optional_for(),
+                }
+            }
+        };
+
+        // The same databases alpm_pkg_compute_requiredby searches.
+        let local = matches!(self.origin(), PackageFrom::LocalDb | PackageFrom::File);
+        let dbs = if local {
+            vec![self.handle.localdb()]
+        } else {
+            self.handle.syncdbs().iter().collect()
+        };
+
+        let mut required_by = self.dependents_in(dbs.iter().copied(), true, false, Some(cache));
+        let mut optional_for = self.dependents_in(dbs.iter().copied(), false, true, Some(cache));
+        if !local {
+            required_by.sort();
+            optional_for.sort();
+        }
+
+        ReverseDeps {
+            required_by,
+            optional_for,
+        }
+    }
+
+    /// Like [`required_by`](Pkg::required_by) but checks the packages in `dbs` instead of
+    /// the package's own database, so sync packages can be checked against the local
+    /// database. Provides are taken into account.
+    ///
+    /// If `optional` is true optional dependencies are checked too, giving the union of
+    /// `required_by` and [`optional_for`](Pkg::optional_for). Pass a `cache` to reuse
+    /// dependency checks across calls.
+    pub fn required_by_in<'d, I>(
+        &self,
+        dbs: I,
+        optional: bool,
+        cache: Option<&mut SatisfierCache>,
+    ) -> AlpmListMut<'a, String>
+    where
+        I: IntoIterator<Item = Db<'d>>,
+    {
+        self.dependents_in(dbs, true, optional, cache)
+    }
+
+    fn dependents_in<'d, I>(
+        &self,
+        dbs: I,
+        depends: bool,
+        optdepends: bool,
+        mut cache: Option<&mut SatisfierCache>,
+    ) -> AlpmListMut<'a, String>
+    where
+        I: IntoIterator<Item = Db<'d>>,
+    {
+        let provides = self.provides();
+        let could_satisfy = |dep: &Dep| {
+            dep.name() == self.name() || provides.iter().any(|p| p.name() == dep.name())
+        };
+        let mut ret = AlpmListMut::new(self.handle);
+
+        for db in dbs {
+            for pkg in db.pkgs() {
+                let deps = pkg.depends();
+                let optdeps = pkg.optdepends();
+                let mut deps = deps
+                    .iter()
+                    .filter(|_| depends)
+                    .chain(optdeps.iter().filter(|_| optdepends));
+                let required = deps.any(|dep| {
+                    could_satisfy(&dep)
+                        && match cache.as_mut() {
+                            Some(cache) => cache.satisfies(self, dep),
+                            None => self.satisfies(dep),
+                        }
+                });
+
+                if required && !ret.iter().any(|n| n == pkg.name()) {
+                    ret.push(pkg.name().to_string());
+                }
+            }
+        }
+
+        ret
+    }
+
     pub fn base64_sig(&self) -> Option<&'a str> {
         let base64_sig = unsafe { alpm_pkg_get_base64_sig(self.pkg) };
         unsafe { from_cstr_optional(base64_sig) }
     }
 
+    /// Whether this package has an install scriptlet.
+    ///
+    /// This is always false for sync packages, see [`try_has_scriptlet`](Pkg::try_has_scriptlet).
     pub fn has_scriptlet(&self) -> bool {
         unsafe { alpm_pkg_has_scriptlet(self.pkg) != 0 }
     }
 
+    /// Whether the data that is not stored in every database has been loaded for this package.
+    ///
+    /// Metadata such as [`groups`](Pkg::groups) and [`licenses`](Pkg::licenses) is always
+    /// loaded, so an empty list means the package has no entries. The file list is only
+    /// loaded for local packages, package files and sync packages from a files database
+    /// (`dbext` set to `.files`). libalpm does not record whether a package file was loaded
+    /// with `full` set so it is assumed to be, use [`LoadedPackage::has_full_data`] to check.
+    pub fn has_full_data(&self) -> bool {
+        match self.origin() {
+            PackageFrom::LocalDb | PackageFrom::File => true,
+            PackageFrom::SyncDb => self.handle.dbext() == ".files",
+        }
+    }
+
+    /// Like [`files`](Pkg::files) but returns `None` if the file list was not loaded.
+    pub fn try_files(&self) -> Option<FileList<'a>> {
+        if self.has_full_data() {
+            Some(self.files())
+        } else {
+            None
+        }
+    }
+
+    /// The [`status`](Backup::status) of each of the package's backup files under `root`,
+    /// in the same order as [`backup`](Pkg::backup).
+    pub fn modified_backups(&self, root: &str) -> Vec<BackupStatus> {
+        self.backup().iter().map(|b| b.status(root)).collect()
+    }
+
+    /// Like [`backup`](Pkg::backup) but returns `None` for sync packages.
+    pub fn try_backup(&self) -> Option<AlpmList<'a, Backup>> {
+        match self.origin() {
+            PackageFrom::SyncDb => None,
+            _ => Some(self.backup()),
+        }
+    }
+
+    /// Like [`has_scriptlet`](Pkg::has_scriptlet) but returns `None` for sync packages.
+    pub fn try_has_scriptlet(&self) -> Option<bool> {
+        match self.origin() {
+            PackageFrom::SyncDb => None,
+            _ => Some(self.has_scriptlet()),
+        }
+    }
+
     pub fn sig(&self) -> Result<Signature> {
         let mut sig = ptr::null_mut();
         let mut len = 0;
@@ -286,13 +559,288 @@ impl<'a> Pkg<'a> {
         let sig = Signature { sig, len };
         Ok(sig)
     }
+
+    /// Checks if this package satisfies `dep`, either by its own name and version or through
+    /// one of its provides.
+    #[doc(alias = "depcmp")]
+    pub fn satisfies<D: AsDep>(&self, dep: D) -> bool {
+        let dep = CString::new(dep.as_dep().to_string()).unwrap();
+        let list = unsafe { once(*self).into_raw_alpm_list() };
+        let pkg = unsafe { alpm_find_satisfier(list.list(), dep.as_ptr()) };
+        !pkg.is_null()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::SigLevel;
-    use std::io::Read;
+    use crate::{ChangeLogEntry, Depend, SigLevel};
+    use std::fs;
+    use std::io::{BufRead, Read};
+
+    #[test]
+    #[allow(clippy::mutable_key_type)]
+    fn test_non_utf8() {
+        use std::collections::HashSet;
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        fn replace(data: &[u8], old: &[u8], new: &[u8]) -> Vec<u8> {
+            let pos = data.windows(old.len()).position(|w| w == old).unwrap();
+            let mut crafted = data[..pos].to_vec();
+            crafted.extend_from_slice(new);
+            crafted.extend_from_slice(&data[pos + old.len()..]);
+            crafted
+        }
+
+        // libalpm takes the version of a local package from its directory name.
+        let tmp = TempDir::new("non-utf8");
+        let dbpath = tmp.local_db(&["vifm-0.10-1"]);
+        let pkgdir = dbpath
+            .join("local")
+            .join(OsStr::from_bytes(b"vifm-0.10\xe9-1"));
+        fs::rename(dbpath.join("local").join("vifm-0.10-1"), &pkgdir).unwrap();
+
+        let desc = fs::read("tests/db/local/vifm-0.10-1/desc").unwrap();
+        let desc = replace(
+            &desc,
+            b"A file manager with curses interface",
+            b"caf\xe9 manager",
+        );
+        let desc = replace(&desc, b"%VERSION%\n0.10-1\n", b"%VERSION%\n0.10\xe9-1\n");
+        fs::write(pkgdir.join("desc"), desc).unwrap();
+
+        let mut files = fs::read("tests/db/local/vifm-0.10-1/files").unwrap();
+        let pos = files.windows(2).position(|w| w == b"\n\n").unwrap();
+        files.splice(pos + 1..pos + 1, b"usr/share/caf\xe9\n".iter().copied());
+        fs::write(pkgdir.join("files"), files).unwrap();
+
+        let handle = Alpm::new("/", dbpath.to_str().unwrap()).unwrap();
+        let pkg = handle.localdb().pkg("vifm").unwrap();
+        assert_eq!(pkg.desc(), Some("caf"));
+        assert!(pkg.desc_bytes().unwrap().starts_with(b"caf\xe9 manager"));
+
+        let files = pkg.files();
+        let file = files
+            .files()
+            .iter()
+            .find(|f| f.name_bytes() == b"usr/share/caf\xe9")
+            .unwrap();
+        assert_eq!(file.name(), "usr/share/caf");
+
+        assert_eq!(pkg.version().as_bytes(), b"0.10\xe9-1");
+        assert_eq!(pkg.version().as_str(), "0.10");
+        assert_eq!(pkg.to_string(), "vifm-0.10");
+        let mut set = HashSet::new();
+        set.insert(pkg);
+        assert!(set.contains(&pkg));
+    }
+
+    #[test]
+    #[allow(clippy::mutable_key_type)]
+    fn test_pkg_eq_hash() {
+        use std::collections::HashSet;
+
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let core = handle.register_syncdb("core", SigLevel::NONE).unwrap();
+
+        let mut set = HashSet::new();
+        set.insert(handle.localdb().pkg("bash").unwrap());
+        set.insert(handle.localdb().pkg("bash").unwrap());
+        assert_eq!(set.len(), 1);
+
+        // Same name, different version and db.
+        set.insert(core.pkg("bash").unwrap());
+        assert_eq!(set.len(), 2);
+        assert_ne!(
+            handle.localdb().pkg("bash").unwrap(),
+            core.pkg("bash").unwrap()
+        );
+
+        let pkgs = handle
+            .localdb()
+            .pkgs()
+            .iter()
+            .chain(handle.localdb().pkgs())
+            .map(|p| *p)
+            .collect::<HashSet<Pkg>>();
+        assert_eq!(pkgs.len(), handle.localdb().pkgs().len());
+    }
+
+    #[test]
+    fn test_modified_backups() {
+        let tmp = TempDir::new("modified-backups");
+        let root = tmp.join("root");
+        let dbpath = tmp.local_db(&["pacman-5.1.3-1"]);
+        let pkgdir = dbpath.join("local").join("pacman-5.1.3-1");
+        fs::create_dir_all(root.join("etc")).unwrap();
+
+        let conf = root.join("etc").join("pacman.conf");
+        fs::write(&conf, "[options]\n").unwrap();
+        let md5 = crate::compute_md5sum(conf.to_str().unwrap()).unwrap();
+        fs::write(root.join("etc").join("makepkg.conf"), "edited\n").unwrap();
+
+        let files = fs::read_to_string("tests/db/local/pacman-5.1.3-1/files").unwrap();
+        let files = files
+            .replace(
+                "etc/pacman.conf\t(null)",
+                &format!("etc/pacman.conf\t{}", md5),
+            )
+            .replace(
+                "etc/makepkg.conf\t(null)",
+                &format!("etc/makepkg.conf\t{}", md5),
+            )
+            .replace("%BACKUP%\n", "%BACKUP%\netc/gone.conf\tffff\n");
+        fs::write(pkgdir.join("files"), files).unwrap();
+
+        let handle = Alpm::new("/", dbpath.to_str().unwrap()).unwrap();
+        let pkg = handle.localdb().pkg("pacman").unwrap();
+        let names = pkg
+            .backup()
+            .iter()
+            .map(|b| b.name().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            ["etc/gone.conf", "etc/pacman.conf", "etc/makepkg.conf"]
+        );
+        assert_eq!(
+            pkg.modified_backups(root.to_str().unwrap()),
+            [
+                BackupStatus::Missing,
+                BackupStatus::Unmodified,
+                BackupStatus::Modified
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reverse_deps() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let localdb = handle.localdb();
+
+        let perl = localdb.pkg("perl").unwrap();
+        let rdeps = perl.reverse_deps(None);
+        let required = perl.required_by();
+        let optional = perl.optional_for();
+        assert!(!rdeps.is_empty());
+        assert_eq!(
+            rdeps.required_by().iter().collect::<Vec<_>>(),
+            required.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            rdeps.optional_for().iter().collect::<Vec<_>>(),
+            optional.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(rdeps.required_by_count(), perl.required_by_count());
+        assert_eq!(rdeps.optional_for_count(), perl.optional_for_count());
+        assert_eq!(perl.required_by_count(), required.len());
+
+        let linux = localdb.pkg("linux").unwrap();
+        let rdeps = linux.reverse_deps(None);
+        assert_eq!(rdeps.required_by_count(), linux.required_by().len());
+    }
+
+    #[test]
+    fn test_reverse_deps_cache() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let core = handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        let localdb = handle.localdb();
+        let mut cache = SatisfierCache::from_db(localdb);
+
+        let glibc = localdb.pkg("glibc").unwrap();
+        let rdeps = glibc.reverse_deps(None);
+        let cached = glibc.reverse_deps(Some(&mut cache));
+        assert_eq!(
+            cached.required_by().iter().collect::<Vec<_>>(),
+            rdeps.required_by().iter().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            cached.optional_for().iter().collect::<Vec<_>>(),
+            rdeps.optional_for().iter().collect::<Vec<_>>()
+        );
+        // Most packages depend on plain "glibc", only the first check is a miss.
+        assert!(cache.hits() > 0);
+
+        let hits = cache.hits();
+        let misses = cache.misses();
+        let found = glibc.required_by_in(Some(localdb), false, Some(&mut cache));
+        assert_eq!(
+            found.iter().collect::<Vec<_>>(),
+            rdeps.required_by().iter().collect::<Vec<_>>()
+        );
+        assert_eq!(cache.misses(), misses);
+        assert!(cache.hits() > hits);
+
+        let bash = core.pkg("bash").unwrap();
+        let rdeps = bash.reverse_deps(None);
+        let cached = bash.reverse_deps(Some(&mut cache));
+        assert_eq!(
+            cached.required_by().iter().collect::<Vec<_>>(),
+            rdeps.required_by().iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_required_by_in() {
+        fn sorted(list: AlpmListMut<String>) -> Vec<String> {
+            let mut list = list.into_iter().collect::<Vec<_>>();
+            list.sort();
+            list.dedup();
+            list
+        }
+
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let core = handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        let extra = handle.register_syncdb("extra", SigLevel::NONE).unwrap();
+        let localdb = handle.localdb();
+
+        let perl = localdb.pkg("perl").unwrap();
+        let required = sorted(perl.required_by());
+        assert_eq!(
+            sorted(perl.required_by_in(Some(localdb), false, None)),
+            required
+        );
+
+        let mut all = perl.required_by();
+        all.extend(perl.optional_for().iter());
+        assert_eq!(
+            sorted(perl.required_by_in(Some(localdb), true, None)),
+            sorted(all)
+        );
+
+        // The sync bash is checked against the local packages, including ones that
+        // depend on sh.
+        let bash = core.pkg("bash").unwrap();
+        let local_bash = localdb.pkg("bash").unwrap();
+        let found = sorted(bash.required_by_in(Some(localdb), false, None));
+        assert!(found.iter().any(|n| n == "autoconf"));
+        assert_eq!(found, sorted(local_bash.required_by()));
+
+        let ostree = extra.pkg("ostree").unwrap();
+        let found = ostree.required_by_in(vec![core, extra], false, None);
+        assert!(found.iter().any(|n| n == "flatpak"));
+    }
+
+    #[test]
+    fn test_satisfies() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let db = handle.register_syncdb("extra", SigLevel::NONE).unwrap();
+        let pkg = db.pkg("ffmpeg").unwrap();
+
+        assert!(pkg.satisfies(Depend::new("ffmpeg")));
+        assert!(pkg.satisfies(Depend::new("ffmpeg=1:4.1.3-1")));
+        assert!(!pkg.satisfies(Depend::new("ffmpeg=4.1.3-1")));
+        assert!(!pkg.satisfies(Depend::new("ffmpeg>=1:5")));
+        assert!(pkg.satisfies(Depend::new("libavcodec.so=58-64")));
+        assert!(pkg.satisfies(Depend::new("libavcodec.so>=58")));
+        assert!(!pkg.satisfies(Depend::new("libavcodec.so>=59")));
+        assert!(!pkg.satisfies(Depend::new("bash")));
+
+        let dep = Depend::new("libavcodec.so=58-64");
+        assert!(dep.satisfied_by(&pkg));
+        assert!(!Depend::new("bash").satisfied_by(&pkg));
+    }
 
     #[test]
     fn test_depends() {
@@ -310,6 +858,15 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_display() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let db = handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        let pkg = db.pkg("linux").unwrap();
+        assert_eq!(pkg.to_string(), "linux-5.1.8.arch1-1");
+        assert_eq!(pkg.as_pkg().to_string(), "linux-5.1.8.arch1-1");
+    }
+
     #[test]
     fn test_files() {
         let handle = Alpm::new("/", "tests/db").unwrap();
@@ -367,4 +924,166 @@ mod tests {
         changelog.read_to_string(&mut s).unwrap();
         assert!(s.contains("2010-02-15 Jaroslav Lichtblau <svetlemodry@archlinux.org>"));
     }
+
+    #[test]
+    fn test_changelog_lines() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let pkg = handle.localdb().pkg("vifm").unwrap();
+
+        let mut s = String::new();
+        pkg.changelog().unwrap().read_to_string(&mut s).unwrap();
+
+        let changelog = pkg.changelog().unwrap();
+        let lines = changelog.lines().collect::<std::io::Result<Vec<_>>>();
+        let lines = lines.unwrap();
+        assert_eq!(
+            lines[0],
+            "2018-11-12 Jaroslav Lichtblau <svetlemodry@archlinux.org>"
+        );
+        assert_eq!(lines[1], "\t* vifm 0.10-1");
+        assert!(lines
+            .iter()
+            .any(|l| l == "2010-02-15 Jaroslav Lichtblau <svetlemodry@archlinux.org>"));
+        assert_eq!(lines, s.lines().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_changelog_bufread() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let pkg = handle.localdb().pkg("vifm").unwrap();
+
+        let mut expected = Vec::new();
+        pkg.changelog().unwrap().read_to_end(&mut expected).unwrap();
+
+        let mut changelog = pkg.changelog().unwrap();
+        let mut line = String::new();
+        changelog.read_line(&mut line).unwrap();
+        assert_eq!(
+            line,
+            "2018-11-12 Jaroslav Lichtblau <svetlemodry@archlinux.org>\n"
+        );
+        let mut rest = Vec::new();
+        let chunk = changelog.read_chunk(4).unwrap();
+        rest.extend(chunk);
+        changelog.read_to_end(&mut rest).unwrap();
+        assert_eq!(line.len() + rest.len(), expected.len());
+        assert_eq!(&expected[line.len()..], rest.as_slice());
+    }
+
+    #[test]
+    fn test_changelog_reset() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let pkg = handle.localdb().pkg("vifm").unwrap();
+        let mut changelog = pkg.changelog().unwrap();
+
+        let mut first = String::new();
+        changelog.read_to_string(&mut first).unwrap();
+        changelog.reset().unwrap();
+        let mut second = String::new();
+        changelog.read_to_string(&mut second).unwrap();
+        assert!(!first.is_empty());
+        assert_eq!(first, second);
+
+        changelog.reset().unwrap();
+        let mut line = String::new();
+        changelog.read_line(&mut line).unwrap();
+        changelog.reset().unwrap();
+        let mut third = String::new();
+        changelog.read_to_string(&mut third).unwrap();
+        assert_eq!(first, third);
+    }
+
+    #[test]
+    fn test_changelog_entries() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let pkg = handle.localdb().pkg("vifm").unwrap();
+
+        let entries = pkg.changelog().unwrap().entries();
+        let entries = entries.collect::<std::io::Result<Vec<_>>>().unwrap();
+        assert_eq!(entries.len(), 23);
+        assert_eq!(
+            entries[0],
+            ChangeLogEntry::Entry {
+                date: "2018-11-12".into(),
+                author: "Jaroslav Lichtblau <svetlemodry@archlinux.org>".into(),
+                body: vec!["\t* vifm 0.10-1".into()],
+            }
+        );
+        match &entries[8] {
+            ChangeLogEntry::Entry { date, body, .. } => {
+                assert_eq!(date, "2015-07-10");
+                assert_eq!(body, &["\t* vifm 0.8-1", "\t* .install file added"]);
+            }
+            ChangeLogEntry::Raw(_) => panic!("expected an entry"),
+        }
+        assert!(entries
+            .iter()
+            .all(|e| matches!(e, ChangeLogEntry::Entry { .. })));
+
+        let mut partial = pkg.changelog().unwrap().entries();
+        assert!(partial.next().is_some());
+        drop(partial);
+    }
+
+    #[test]
+    fn test_changelog_chunks() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let db = handle.localdb();
+        let pkg = db.pkg("vifm").unwrap();
+
+        let mut expected = Vec::new();
+        pkg.changelog().unwrap().read_to_end(&mut expected).unwrap();
+
+        let mut chunked = Vec::new();
+        let mut changelog = pkg.changelog().unwrap();
+        while let Some(chunk) = changelog.read_chunk(16) {
+            assert!(chunk.len() <= 16);
+            chunked.extend(chunk);
+        }
+
+        assert!(!chunked.is_empty());
+        assert_eq!(chunked, expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "read_chunk max must be greater than 0")]
+    fn test_changelog_read_chunk_zero() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let pkg = handle.localdb().pkg("vifm").unwrap();
+        let _ = pkg.changelog().unwrap().read_chunk(0);
+    }
+
+    #[test]
+    fn test_full_data() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let db = handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        let sync = db.pkg("linux").unwrap();
+        let local = handle.localdb().pkg("linux").unwrap();
+
+        assert!(!sync.has_full_data());
+        assert!(sync.try_files().is_none());
+        assert!(sync.try_backup().is_none());
+        assert!(sync.try_has_scriptlet().is_none());
+        assert!(sync.changelog().is_err());
+        assert_eq!(sync.groups().iter().collect::<Vec<_>>(), ["base"]);
+
+        assert!(local.has_full_data());
+        assert!(!local.try_files().unwrap().is_empty());
+        assert!(local.try_backup().is_some());
+        assert_eq!(local.try_has_scriptlet(), Some(true));
+        assert_eq!(local.groups().iter().collect::<Vec<_>>(), ["base"]);
+
+        let vifm = handle.localdb().pkg("vifm").unwrap();
+        assert_eq!(vifm.try_has_scriptlet(), Some(false));
+        assert!(vifm.changelog().is_ok());
+
+        let file = "tests/pacman-5.1.3-1-x86_64.pkg.tar.xz";
+        let partial = handle.pkg_load(file, false, SigLevel::NONE).unwrap();
+        assert!(!partial.has_full_data());
+        assert!(partial.try_files().is_none());
+        let full = handle.pkg_load(file, true, SigLevel::NONE).unwrap();
+        assert!(full.has_full_data());
+        assert!(full.try_files().is_some());
+        assert!(partial.pkg().has_full_data());
+    }
 }
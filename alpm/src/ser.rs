@@ -0,0 +1,255 @@
+use crate::{
+    Dep, Depend, Package, PackageReason, PackageValidation, Pkg, SigLevel, Usage, Ver, Version,
+};
+
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize, Serializer};
+
+/// An owned copy of a package's metadata.
+///
+/// [`Pkg`] and [`Package`] serialize as this record. Unlike them it can also be
+/// deserialized, so it can be stored and read back without a handle.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PkgInfo {
+    pub name: String,
+    pub version: String,
+    pub desc: Option<String>,
+    pub url: Option<String>,
+    pub packager: Option<String>,
+    pub arch: Option<String>,
+    pub licenses: Vec<String>,
+    pub groups: Vec<String>,
+    pub depends: Vec<String>,
+    pub optdepends: Vec<String>,
+    pub provides: Vec<String>,
+    pub conflicts: Vec<String>,
+    pub replaces: Vec<String>,
+    pub size: i64,
+    pub isize: i64,
+    pub download_size: i64,
+    pub build_date: i64,
+    pub install_date: Option<i64>,
+    pub reason: PackageReason,
+    pub validation: Vec<String>,
+}
+
+fn validation_names(validation: PackageValidation) -> Vec<String> {
+    [
+        (PackageValidation::NONE, "none"),
+        (PackageValidation::MD5SUM, "md5"),
+        (PackageValidation::SHA256SUM, "sha256"),
+        (PackageValidation::SIGNATURE, "pgp"),
+    ]
+    .iter()
+    .filter(|(flag, _)| validation.contains(*flag))
+    .map(|(_, name)| name.to_string())
+    .collect()
+}
+
+impl<'a> From<&Pkg<'a>> for PkgInfo {
+    fn from(pkg: &Pkg<'a>) -> PkgInfo {
+        let to_strings = |list: crate::AlpmList<Dep>| list.iter().map(|d| d.to_string()).collect();
+
+        PkgInfo {
+            name: pkg.name().to_string(),
+            version: pkg.version().to_string(),
+            desc: pkg.desc().map(|s| s.to_string()),
+            url: pkg.url().map(|s| s.to_string()),
+            packager: pkg.packager().map(|s| s.to_string()),
+            arch: pkg.arch().map(|s| s.to_string()),
+            licenses: pkg.licenses().iter().map(|s| s.to_string()).collect(),
+            groups: pkg.groups().iter().map(|s| s.to_string()).collect(),
+            depends: to_strings(pkg.depends()),
+            optdepends: to_strings(pkg.optdepends()),
+            provides: to_strings(pkg.provides()),
+            conflicts: to_strings(pkg.conflicts()),
+            replaces: to_strings(pkg.replaces()),
+            size: pkg.size(),
+            isize: pkg.isize(),
+            download_size: pkg.download_size(),
+            build_date: pkg.build_date(),
+            install_date: pkg.install_date(),
+            reason: pkg.reason(),
+            validation: validation_names(pkg.validation()),
+        }
+    }
+}
+
+impl<'a> From<&Package<'a>> for PkgInfo {
+    fn from(pkg: &Package<'a>) -> PkgInfo {
+        PkgInfo::from(&pkg.pkg)
+    }
+}
+
+/// Serializes the package's metadata as a [`PkgInfo`].
+impl<'a> Serialize for Pkg<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        PkgInfo::from(self).serialize(serializer)
+    }
+}
+
+impl<'a> Serialize for Package<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.pkg.serialize(serializer)
+    }
+}
+
+/// Serializes as the dependency string, e.g. `"glibc>=2.28"`.
+impl<'a> Serialize for Dep<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl Serialize for Depend {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl Serialize for Ver {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl Serialize for Version {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+/// Serializes as `"explicit"` or `"dependency"`.
+impl Serialize for PackageReason {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for PackageReason {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        match s.as_str() {
+            "explicit" => Ok(PackageReason::Explicit),
+            "dependency" => Ok(PackageReason::Depend),
+            _ => Err(de::Error::unknown_variant(&s, &["explicit", "dependency"])),
+        }
+    }
+}
+
+/// Serializes as a pacman.conf `SigLevel` value, see [`SigLevel::to_conf_string`].
+///
+/// [`SigLevel::USE_DEFAULT`] is kept and serializes as `"UseDefault"`.
+impl Serialize for SigLevel {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_conf_string())
+    }
+}
+
+/// Serializes as a list of the enabled usages, e.g. `["sync", "search"]`.
+impl Serialize for Usage {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let names = [
+            (Usage::SYNC, "sync"),
+            (Usage::SEARCH, "search"),
+            (Usage::INSTALL, "install"),
+            (Usage::UPGRADE, "upgrade"),
+        ];
+        serializer.collect_seq(
+            names
+                .iter()
+                .filter(|(flag, _)| self.contains(*flag))
+                .map(|(_, name)| name),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Alpm, SyncDbConfig};
+
+    #[test]
+    fn test_serialize_pkg() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let pkg = handle.localdb().pkg("linux").unwrap();
+
+        let json = serde_json::to_value(pkg).unwrap();
+        assert_eq!(json["name"], "linux");
+        assert_eq!(json["version"], pkg.version().as_str());
+        assert_eq!(json["groups"], serde_json::json!(["base"]));
+        assert_eq!(json["reason"], "explicit");
+        assert_eq!(json["validation"], serde_json::json!(["sha256"]));
+        assert_eq!(json["install_date"], pkg.install_date().unwrap());
+        assert_eq!(json["arch"], "x86_64");
+        assert_eq!(
+            json["depends"].as_array().unwrap().len(),
+            pkg.depends().len()
+        );
+        assert_eq!(json["depends"][0], "coreutils");
+
+        let bash = handle.localdb().pkg("bash").unwrap();
+        let json = serde_json::to_value(bash).unwrap();
+        assert_eq!(json["reason"], "dependency");
+        assert_eq!(json["provides"], serde_json::json!(["sh"]));
+    }
+
+    #[test]
+    fn test_serialize_null_fields() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let db = handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        let pkg = db.pkg("linux").unwrap();
+
+        let json = serde_json::to_value(pkg).unwrap();
+        assert!(json["install_date"].is_null());
+        assert_eq!(json["licenses"], serde_json::json!(["GPL2"]));
+    }
+
+    #[test]
+    fn test_pkg_info_round_trip() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let pkg = handle.localdb().pkg("bash").unwrap();
+
+        let info = PkgInfo::from(&pkg);
+        let json = serde_json::to_string(&info).unwrap();
+        assert_eq!(json, serde_json::to_string(&pkg).unwrap());
+        let back: PkgInfo = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, info);
+        assert_eq!(back.reason, PackageReason::Depend);
+
+        assert!(serde_json::from_str::<PackageReason>("\"other\"").is_err());
+    }
+
+    #[test]
+    fn test_serialize_types() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let pkg = handle.localdb().pkg("linux").unwrap();
+
+        let dep = pkg.depends().first().unwrap();
+        assert_eq!(serde_json::to_value(dep).unwrap(), "coreutils");
+        let depend = Depend::new("glibc>=2.28");
+        assert_eq!(serde_json::to_value(&depend).unwrap(), "glibc>=2.28");
+        assert_eq!(
+            serde_json::to_value(pkg.version()).unwrap(),
+            pkg.version().as_str()
+        );
+        assert_eq!(
+            serde_json::to_value(Version::new("1.0-1")).unwrap(),
+            "1.0-1"
+        );
+
+        let level = SigLevel::PACKAGE | SigLevel::PACKAGE_OPTIONAL;
+        assert_eq!(serde_json::to_value(level).unwrap(), level.to_conf_string());
+        assert_eq!(
+            serde_json::to_value(SigLevel::USE_DEFAULT).unwrap(),
+            "UseDefault"
+        );
+        let db = SyncDbConfig {
+            name: "extra".to_string(),
+            servers: Vec::new(),
+            siglevel: SigLevel::USE_DEFAULT,
+            usage: Usage::ALL,
+        };
+        assert_eq!(serde_json::to_value(&db).unwrap()["siglevel"], "UseDefault");
+    }
+}
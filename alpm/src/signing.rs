@@ -1,11 +1,10 @@
 use crate::utils::*;
-use crate::{free, Alpm, AlpmListMut, Db, Package, Result};
+use crate::{Alpm, AlpmListMut, Db, Error, Package, PackageValidation, Pkg, Result, Signature};
 
 use alpm_sys::_alpm_sigstatus_t::*;
 use alpm_sys::_alpm_sigvalidity_t::*;
 use alpm_sys::*;
 
-use std::ffi::{c_void, CString};
 use std::mem::transmute;
 use std::{fmt, ptr, slice};
 
@@ -23,19 +22,7 @@ impl std::error::Error for SignatureDecodeError {}
 pub fn decode_signature<S: Into<Vec<u8>>>(
     b64: S,
 ) -> std::result::Result<Vec<u8>, SignatureDecodeError> {
-    let b64 = CString::new(b64).unwrap();
-    let mut data = ptr::null_mut();
-    let mut len = 0;
-    let ret = unsafe { alpm_decode_signature(b64.as_ptr(), &mut data, &mut len) };
-    if ret != 0 {
-        return Err(SignatureDecodeError);
-    }
-
-    let buff = unsafe { slice::from_raw_parts(data, len) };
-    let v = buff.to_owned();
-
-    unsafe { free(data as *mut c_void) };
-    Ok(v)
+    Signature::decode(b64).map(|sig| sig.to_vec())
 }
 
 #[repr(u32)]
@@ -190,7 +177,69 @@ impl SigList {
     }
 }
 
+/// Why a signature check failed.
+#[derive(Debug)]
+pub enum SigCheckError {
+    /// There is no signature to check.
+    Missing,
+    /// At least one signature was made by a key that is not in the keyring.
+    KeyUnknown(SigList),
+    /// At least one signature is invalid, expired or made by an untrusted key.
+    Invalid(SigList),
+    /// The check could not be performed.
+    Alpm(Error),
+}
+
+impl fmt::Display for SigCheckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SigCheckError::Missing => fmt::Display::fmt(&Error::SigMissing, f),
+            SigCheckError::KeyUnknown(_) => f.write_str("unknown public key"),
+            SigCheckError::Invalid(_) => fmt::Display::fmt(&Error::SigInvalid, f),
+            SigCheckError::Alpm(err) => fmt::Display::fmt(err, f),
+        }
+    }
+}
+
+impl std::error::Error for SigCheckError {}
+
+impl SigCheckError {
+    fn new(handle: &Alpm, ret: i32, siglist: SigList) -> std::result::Result<SigList, Self> {
+        match ret {
+            0 => Ok(siglist),
+            1 => {
+                let unknown = siglist
+                    .results()
+                    .iter()
+                    .any(|r| r.status() == SigStatus::KeyUnknown);
+                if unknown {
+                    Err(SigCheckError::KeyUnknown(siglist))
+                } else {
+                    Err(SigCheckError::Invalid(siglist))
+                }
+            }
+            _ => match handle.last_error() {
+                Error::SigMissing => Err(SigCheckError::Missing),
+                err => Err(SigCheckError::Alpm(err)),
+            },
+        }
+    }
+}
+
 impl<'a> Package<'a> {
+    /// Checks the PGP signature of the package, classifying any failure.
+    pub fn verify_signature(&self) -> std::result::Result<SigList, SigCheckError> {
+        let mut siglist = SigList::new();
+        let ret = unsafe { alpm_pkg_check_pgp_signature(self.pkg.pkg, &mut siglist.inner) };
+        SigCheckError::new(self.handle, ret, siglist)
+    }
+
+    /// Checks the PGP signature of the package file.
+    ///
+    /// Returns whether the signature is valid along with the per-signature
+    /// results. libalpm takes no [`SigLevel`](crate::SigLevel) here; the
+    /// handle's configured keyring decides what is trusted.
+    #[doc(alias = "pkg_check_pgp_signature")]
     pub fn check_signature(&self) -> Result<(bool, SigList)> {
         let mut siglist = SigList::new();
         let ret = unsafe { alpm_pkg_check_pgp_signature(self.pkg.pkg, &mut siglist.inner) };
@@ -205,6 +254,13 @@ impl<'a> Package<'a> {
 }
 
 impl<'a> Db<'a> {
+    /// Checks the PGP signature of the database, classifying any failure.
+    pub fn verify_signature(&self) -> std::result::Result<SigList, SigCheckError> {
+        let mut siglist = SigList::new();
+        let ret = unsafe { alpm_db_check_pgp_signature(self.db, &mut siglist.inner) };
+        SigCheckError::new(self.handle, ret, siglist)
+    }
+
     pub fn check_signature(&self) -> Result<(bool, SigList)> {
         let mut siglist = SigList::new();
         let ret = unsafe { alpm_db_check_pgp_signature(self.db, &mut siglist.inner) };
@@ -218,13 +274,78 @@ impl<'a> Db<'a> {
     }
 }
 
+/// How a package's integrity was verified, suitable for display in package info.
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
+pub struct VerificationSummary {
+    validation: PackageValidation,
+    has_signature: bool,
+    signature_valid: Option<bool>,
+}
+
+impl fmt::Display for VerificationSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.validation.describe())?;
+
+        match self.signature_valid {
+            Some(true) => f.write_str(", signature valid"),
+            Some(false) => f.write_str(", signature invalid"),
+            None if self.has_signature => f.write_str(", signature available"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl VerificationSummary {
+    /// How the package was validated when it was installed.
+    pub fn validation(&self) -> PackageValidation {
+        self.validation
+    }
+
+    /// Whether the package has a stored signature.
+    pub fn has_signature(&self) -> bool {
+        self.has_signature
+    }
+
+    /// The result of checking the signature now.
+    ///
+    /// `None` if the signature was not checked or the check could not be performed.
+    pub fn signature_valid(&self) -> Option<bool> {
+        self.signature_valid
+    }
+}
+
+impl<'a> Pkg<'a> {
+    /// Summarizes how this package was validated.
+    ///
+    /// If `check` is true the package signature is also checked now.
+    pub fn verification_summary(&self, check: bool) -> VerificationSummary {
+        let signature_valid = if check {
+            let mut siglist = SigList::new();
+            let ret = unsafe { alpm_pkg_check_pgp_signature(self.pkg, &mut siglist.inner) };
+            match ret {
+                0 => Some(true),
+                1 => Some(false),
+                _ => None,
+            }
+        } else {
+            None
+        };
+
+        VerificationSummary {
+            validation: self.validation(),
+            has_signature: self.base64_sig().is_some(),
+            signature_valid,
+        }
+    }
+}
+
 impl Alpm {
     pub fn extract_keyid<'a, S: Into<Vec<u8>>>(
         &'a self,
         ident: S,
         sig: &[u8],
     ) -> Result<AlpmListMut<'a, String>> {
-        let ident = CString::new(ident).unwrap();
+        let ident = cstring(ident)?;
         let mut keys = ptr::null_mut();
 
         let ret = unsafe {
@@ -241,3 +362,91 @@ impl Alpm {
         Ok(AlpmListMut::from_parts(self, keys))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SigLevel;
+
+    #[test]
+    fn test_verify_signature_missing() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let db = handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        let err = db.verify_signature().unwrap_err();
+        assert!(matches!(err, SigCheckError::Missing));
+        assert_eq!(err.to_string(), Error::SigMissing.to_string());
+    }
+
+    #[test]
+    fn test_check_signature_pkg() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+
+        let pkg = handle.localdb().pkg("vifm").unwrap();
+        assert!(pkg.check_signature().is_err());
+
+        // The sync package has an embedded signature but its file isn't cached.
+        let db = handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        let pkg = db.pkg("linux").unwrap();
+        assert!(pkg.base64_sig().is_some());
+        match pkg.check_signature() {
+            Ok((valid, siglist)) => {
+                assert!(!valid);
+                assert!(siglist
+                    .results()
+                    .iter()
+                    .all(|r| r.status() != SigStatus::Valid));
+            }
+            Err(err) => assert_ne!(err, Error::Ok),
+        }
+    }
+
+    #[test]
+    fn test_verification_summary() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let db = handle.localdb();
+
+        let summary = db.pkg("pacman").unwrap().verification_summary(false);
+        assert_eq!(summary.validation(), PackageValidation::SIGNATURE);
+        assert!(!summary.has_signature());
+        assert_eq!(summary.to_string(), "Signature");
+
+        let summary = db.pkg("filesystem").unwrap().verification_summary(false);
+        assert_eq!(summary.to_string(), "SHA-256 Sum");
+
+        let summary = db.pkg("expac-git").unwrap().verification_summary(false);
+        assert_eq!(summary.to_string(), "None");
+
+        let core = handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        let summary = core.pkg("linux").unwrap().verification_summary(false);
+        assert!(summary.has_signature());
+        assert_eq!(summary.signature_valid(), None);
+        assert_eq!(summary.to_string(), "Unknown, signature available");
+    }
+
+    #[test]
+    fn test_verification_summary_display() {
+        let summary = |validation, has_signature, signature_valid| VerificationSummary {
+            validation,
+            has_signature,
+            signature_valid,
+        };
+
+        let md5 = summary(PackageValidation::MD5SUM, false, None);
+        assert_eq!(md5.to_string(), "MD5 Sum");
+
+        let sums = PackageValidation::MD5SUM | PackageValidation::SHA256SUM;
+        assert_eq!(
+            summary(sums, false, None).to_string(),
+            "MD5 Sum  SHA-256 Sum"
+        );
+
+        let sig = summary(PackageValidation::SIGNATURE, true, Some(true));
+        assert_eq!(sig.to_string(), "Signature, signature valid");
+
+        let sig = summary(PackageValidation::SIGNATURE, true, Some(false));
+        assert_eq!(sig.to_string(), "Signature, signature invalid");
+
+        let none = summary(PackageValidation::NONE, false, None);
+        assert_eq!(none.to_string(), "None");
+    }
+}
@@ -1,13 +1,18 @@
-use crate::{Alpm, AlpmList, AlpmListMut, Db, IntoRawAlpmList, Package, Result};
+use crate::{Alpm, AlpmList, AlpmListMut, Db, IntoRawAlpmList, Package, Pkg, Result};
 
-use std::ffi::CString;
+use crate::utils::*;
 
 use alpm_sys::*;
 
-impl<'a> Package<'a> {
-    pub fn sync_new_version<T: IntoRawAlpmList<'a, Db<'a>>>(&self, dbs: T) -> Option<Package> {
+impl<'a> Pkg<'a> {
+    /// Finds a newer version of this package in the given databases.
+    ///
+    /// The first database containing a package of the same name is used. Like libalpm,
+    /// this does not consider ignorepkg or ignoregroup, use
+    /// [`should_ignore`](Pkg::should_ignore) on the result for that.
+    pub fn sync_new_version<T: IntoRawAlpmList<'a, Db<'a>>>(&self, dbs: T) -> Option<Package<'a>> {
         let dbs = unsafe { dbs.into_raw_alpm_list() };
-        let ret = unsafe { alpm_sync_get_new_version(self.pkg.pkg, dbs.list()) };
+        let ret = unsafe { alpm_sync_get_new_version(self.pkg, dbs.list()) };
 
         if ret.is_null() {
             None
@@ -15,22 +20,79 @@ impl<'a> Package<'a> {
             unsafe { Some(Package::new(self.handle, ret)) }
         }
     }
+}
 
+impl<'a> Pkg<'a> {
+    /// The amount of data that needs to be downloaded to install this package.
+    ///
+    /// Packages already in the cache don't need to be downloaded. This is always 0 for
+    /// packages that do not come from a sync database.
     pub fn download_size(&self) -> i64 {
-        let size = unsafe { alpm_pkg_download_size(self.pkg.pkg) };
+        let size = unsafe { alpm_pkg_download_size(self.pkg) };
         size as i64
     }
 }
 
+impl<'a> AlpmList<'a, Package<'a>> {
+    /// The sum of [`download_size`](Pkg::download_size) for every package in the list.
+    pub fn download_size(&self) -> i64 {
+        self.iter().map(|pkg| pkg.download_size()).sum()
+    }
+}
+
 impl Alpm {
+    /// Finds the local packages that have a newer version in the sync databases.
+    ///
+    /// Returns `(local, new)` pairs in local database order. See
+    /// [`sync_new_version`](Pkg::sync_new_version).
+    pub fn check_updates(&self) -> Vec<(Package<'_>, Package<'_>)> {
+        let dbs = self.syncdbs();
+        self.localdb()
+            .pkgs()
+            .iter()
+            .filter_map(|pkg| pkg.sync_new_version(dbs).map(|new| (pkg, new)))
+            .collect()
+    }
+
+    /// Finds the packages in a group across `dbs`.
+    ///
+    /// Databases are searched in order and packages within a database are in the order of
+    /// [`Group::packages`](crate::Group::packages). If a package name appears in multiple
+    /// databases only the first is returned. Ignored packages are skipped.
+    ///
+    /// Fails with [`Error::WrongArgs`](crate::Error::WrongArgs) if the name contains a nul
+    /// byte.
     pub fn find_group_pkgs<'a, S: Into<Vec<u8>>>(
         &'a self,
         dbs: AlpmList<Db>,
         s: S,
-    ) -> AlpmListMut<'a, Package<'a>> {
-        let name = CString::new(s).unwrap();
+    ) -> Result<AlpmListMut<'a, Package<'a>>> {
+        let name = cstring(s)?;
         let ret = unsafe { alpm_find_group_pkgs(dbs.list, name.as_ptr()) };
-        AlpmListMut::from_parts(self, ret)
+        Ok(AlpmListMut::from_parts(self, ret))
+    }
+
+    /// Finds the package called `name` in the sync databases.
+    ///
+    /// The databases are searched in the order they were registered, so a package in an
+    /// earlier database shadows one of the same name in a later database. Provides are not
+    /// considered, see [`pkg_by_name`](Alpm::pkg_by_name).
+    pub fn find_pkg_in_syncdbs<S: Into<Vec<u8>>>(&self, name: S) -> Option<Package<'_>> {
+        let name = name.into();
+        self.syncdbs()
+            .iter()
+            .find_map(|db| db.pkg(name.clone()).ok())
+    }
+
+    /// Finds the package called `name` in the sync databases, falling back to a package
+    /// that provides `name`.
+    ///
+    /// A package matching by name in any database is preferred over a provider. Both
+    /// lookups search the databases in the order they were registered.
+    pub fn pkg_by_name<S: Into<Vec<u8>>>(&self, name: S) -> Option<Package<'_>> {
+        let name = name.into();
+        self.find_pkg_in_syncdbs(name.clone())
+            .or_else(|| self.syncdbs().find_satisfier(name))
     }
 }
 
@@ -40,3 +102,120 @@ impl Alpm {
         self.check_ret(ret)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{Alpm, AlpmListMut, SigLevel};
+
+    #[test]
+    fn test_sync_new_version() {
+        let mut handle = Alpm::new("/", "tests/db").unwrap();
+        handle.register_syncdb("core", SigLevel::NONE).unwrap();
+
+        let bash = handle.localdb().pkg("bash").unwrap();
+        let new = bash.sync_new_version(handle.syncdbs()).unwrap();
+        assert_eq!(bash.version().as_str(), "5.0.002-1");
+        assert_eq!(new.version().as_str(), "5.0.007-1");
+        assert_eq!(new.db().unwrap().name(), "core");
+
+        let linux = handle.localdb().pkg("linux").unwrap();
+        assert!(linux.sync_new_version(handle.syncdbs()).is_none());
+
+        handle.add_ignorepkg("bash").unwrap();
+        let bash = handle.localdb().pkg("bash").unwrap();
+        let new = bash.sync_new_version(handle.syncdbs()).unwrap();
+        assert!(new.should_ignore());
+    }
+
+    #[test]
+    fn test_check_updates() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        assert!(handle.check_updates().is_empty());
+        handle.register_syncdb("core", SigLevel::NONE).unwrap();
+
+        let updates = handle.check_updates();
+        assert!(!updates.is_empty());
+        for (local, new) in &updates {
+            assert_eq!(local.name(), new.name());
+            assert!(new.version() > local.version());
+            assert_eq!(new.db().unwrap().name(), "core");
+        }
+
+        let (_, bash) = updates.iter().find(|(p, _)| p.name() == "bash").unwrap();
+        assert_eq!(bash.version().as_str(), "5.0.007-1");
+        assert!(updates.iter().all(|(p, _)| p.name() != "linux"));
+    }
+
+    #[test]
+    fn test_find_group_pkgs_order() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        handle.register_syncdb("testing", SigLevel::NONE).unwrap();
+        handle.register_syncdb("core", SigLevel::NONE).unwrap();
+
+        let pkgs = handle.find_group_pkgs(handle.syncdbs(), "base").unwrap();
+        let dbs = pkgs
+            .iter()
+            .map(|p| p.db().unwrap().name())
+            .collect::<Vec<_>>();
+        let first_core = dbs.iter().position(|db| *db == "core").unwrap();
+        assert!(dbs[..first_core].iter().all(|db| *db == "testing"));
+        assert!(dbs[first_core..].iter().all(|db| *db == "core"));
+
+        let names = pkgs.iter().map(|p| p.name()).collect::<Vec<_>>();
+        let mut dedup = names.clone();
+        dedup.sort_unstable();
+        dedup.dedup();
+        assert_eq!(dedup.len(), names.len());
+    }
+
+    #[test]
+    fn test_find_pkg_in_syncdbs() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        assert!(handle.find_pkg_in_syncdbs("linux").is_none());
+
+        handle.register_syncdb("testing", SigLevel::NONE).unwrap();
+        handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        handle.register_syncdb("extra", SigLevel::NONE).unwrap();
+
+        let linux = handle.find_pkg_in_syncdbs("linux").unwrap();
+        assert_eq!(linux.name(), "linux");
+        assert_eq!(linux.db().unwrap().name(), "core");
+
+        let curl = handle.find_pkg_in_syncdbs("curl").unwrap();
+        assert_eq!(curl.db().unwrap().name(), "testing");
+
+        assert!(handle.find_pkg_in_syncdbs("sh").is_none());
+        assert!(handle.find_pkg_in_syncdbs("not-a-package").is_none());
+    }
+
+    #[test]
+    fn test_pkg_by_name() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        handle.register_syncdb("extra", SigLevel::NONE).unwrap();
+
+        let linux = handle.pkg_by_name("linux").unwrap();
+        assert_eq!(linux.db().unwrap().name(), "core");
+
+        let sh = handle.pkg_by_name("sh").unwrap();
+        assert_eq!(sh.name(), "bash");
+        assert!(handle.pkg_by_name("not-a-package").is_none());
+    }
+
+    #[test]
+    fn test_download_size() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let db = handle.register_syncdb("core", SigLevel::NONE).unwrap();
+
+        let bash = db.pkg("bash").unwrap();
+        let linux = db.pkg("linux").unwrap();
+        assert_eq!(bash.download_size(), 1573760);
+        assert_eq!(linux.download_size(), 75209420);
+        assert_eq!(handle.localdb().pkg("bash").unwrap().download_size(), 0);
+
+        let mut pkgs = AlpmListMut::new(&handle);
+        pkgs.push(bash);
+        pkgs.push(linux);
+        assert_eq!(pkgs.as_list().download_size(), 1573760 + 75209420);
+    }
+}
@@ -33,7 +33,7 @@ bitflags! {
 impl Alpm {
     pub fn trans_flags(self) -> TransFlag {
         let flags = unsafe { alpm_trans_get_flags(self.handle) };
-        TransFlag::from_bits(flags as u32).unwrap()
+        unsafe { TransFlag::from_bits_unchecked(flags as u32) }
     }
 
     pub fn trans_prepare(&mut self) -> std::result::Result<(), (PrepareResult, Error)> {
@@ -83,16 +83,43 @@ impl Alpm {
         }
     }
 
-    pub fn trans_interrupt(&mut self) -> Result<()> {
-        let ret = unsafe { alpm_trans_interrupt(self.handle) };
-        self.check_ret(ret)
-    }
-
     pub fn trans_add(&self) -> AlpmList<Package> {
         let list = unsafe { alpm_trans_get_add(self.handle) };
         AlpmList::from_parts(self, list)
     }
 
+    /// The total number of bytes that need to be downloaded for the transaction.
+    ///
+    /// See [`Pkg::download_size`](crate::Pkg::download_size).
+    pub fn trans_download_size(&self) -> i64 {
+        self.trans_add().download_size()
+    }
+
+    /// The change in installed size if the transaction is committed, in bytes.
+    ///
+    /// The result is positive if the transaction needs more disk space and negative if it
+    /// frees space. Upgraded packages count as the difference between the new package and
+    /// the installed version, like pacman's "Net Upgrade Size".
+    pub fn trans_install_size(&self) -> i64 {
+        let localdb = self.localdb();
+        let remove = self.trans_remove();
+
+        let added = self
+            .trans_add()
+            .iter()
+            .map(|pkg| {
+                let old = localdb
+                    .pkg(pkg.name())
+                    .ok()
+                    .filter(|old| !remove.contains_pkg(old.name()));
+                pkg.isize() - old.map_or(0, |old| old.isize())
+            })
+            .sum::<i64>();
+        let removed = remove.iter().map(|pkg| pkg.isize()).sum::<i64>();
+
+        added - removed
+    }
+
     pub fn trans_remove(&self) -> AlpmList<Package> {
         let list = unsafe { alpm_trans_get_remove(self.handle) };
         AlpmList::from_parts(self, list)
@@ -105,6 +132,21 @@ impl Alpm {
 }
 
 impl Alpm {
+    /// Asks a running [`trans_commit`](Alpm::trans_commit) to stop at the next safe point.
+    ///
+    /// Fails with [`Error::TransNull`] if there is no transaction and [`Error::TransType`]
+    /// if the transaction is not being committed.
+    ///
+    /// This only sets a flag on the transaction so it may be called from a callback during
+    /// the commit. `Alpm` is not `Sync`, so calling it from a signal handler or another
+    /// thread means going through [`as_alpm_handle_t`](Alpm::as_alpm_handle_t) and
+    /// `alpm_trans_interrupt` directly. The caller must then make sure the handle outlives
+    /// the signal handler and must not touch anything else on the handle from there.
+    pub fn trans_interrupt(&self) -> Result<()> {
+        let ret = unsafe { alpm_trans_interrupt(self.handle) };
+        self.check_ret(ret)
+    }
+
     pub fn trans_init(&self, flags: TransFlag) -> Result<()> {
         let ret = unsafe { alpm_trans_init(self.handle, flags.bits() as i32) };
         self.check_ret(ret)
@@ -153,4 +195,68 @@ mod tests {
         // But we're only testing that the function is called correctly anyway.
         assert!(handle.trans_commit().unwrap_err().1 == Error::Retrieve);
     }
+
+    #[test]
+    fn test_trans_download_size() {
+        let mut handle = Alpm::new("/", "tests/db").unwrap();
+        let db = handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        let bash = db.pkg("bash").unwrap();
+        let linux = db.pkg("linux").unwrap();
+
+        handle
+            .trans_init(TransFlag::DB_ONLY | TransFlag::NO_LOCK)
+            .unwrap();
+        assert_eq!(handle.trans_download_size(), 0);
+        handle.trans_add_pkg(bash).unwrap();
+        handle.trans_add_pkg(linux).unwrap();
+        assert_eq!(handle.trans_download_size(), 1573760 + 75209420);
+        handle.trans_release().unwrap();
+    }
+
+    #[test]
+    fn test_trans_interrupt() {
+        let mut handle = Alpm::new("/", "tests/db").unwrap();
+        assert_eq!(handle.trans_interrupt().unwrap_err(), Error::TransNull);
+
+        handle
+            .trans_init(TransFlag::DB_ONLY | TransFlag::NO_LOCK)
+            .unwrap();
+        assert_eq!(handle.trans_interrupt().unwrap_err(), Error::TransType);
+        handle.trans_release().unwrap();
+    }
+
+    #[test]
+    fn test_trans_install_size() {
+        let mut handle = Alpm::new("/", "tests/db").unwrap();
+        let db = handle.register_syncdb("core", SigLevel::NONE).unwrap();
+        let localdb = handle.localdb();
+
+        let bash = db.pkg("bash").unwrap();
+        let local_bash = localdb.pkg("bash").unwrap();
+        let new = db
+            .pkgs()
+            .iter()
+            .find(|p| localdb.pkg(p.name()).is_err())
+            .unwrap();
+        let vifm = localdb.pkg("vifm").unwrap();
+
+        handle
+            .trans_init(TransFlag::DB_ONLY | TransFlag::NO_LOCK)
+            .unwrap();
+        assert_eq!(handle.trans_install_size(), 0);
+
+        handle.trans_add_pkg(bash).unwrap();
+        assert_eq!(
+            handle.trans_install_size(),
+            bash.isize() - local_bash.isize()
+        );
+
+        handle.trans_add_pkg(new).unwrap();
+        handle.trans_remove_pkg(vifm).unwrap();
+        assert_eq!(
+            handle.trans_install_size(),
+            bash.isize() - local_bash.isize() + new.isize() - vifm.isize()
+        );
+        handle.trans_release().unwrap();
+    }
 }
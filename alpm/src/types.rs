@@ -1,20 +1,25 @@
+use crate::compute_md5sum;
 use crate::utils::*;
 
 #[cfg(not(feature = "git"))]
 use crate::PgpKey;
 use crate::{
     Alpm, AlpmList, AlpmListMut, Conflict, Db, Dep, DependMissing, Error, OwnedConflict,
-    OwnedFileConflict, Package, Pkg,
+    OwnedFileConflict, Package, Pkg, Result, SignatureDecodeError,
 };
 
-use std::ffi::c_void;
+use std::ffi::{c_void, CString};
 use std::fmt;
-use std::io::{self, Read};
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read};
 use std::marker::PhantomData;
 use std::mem::{transmute, ManuallyDrop};
-use std::os::raw::c_uchar;
-use std::slice;
+use std::os::raw::{c_int, c_uchar};
+use std::os::unix::ffi::OsStringExt;
+use std::path::Path;
+use std::str::FromStr;
 use std::{cmp::Ordering, ops::Deref};
+use std::{ptr, slice};
 
 use _alpm_db_usage_t::*;
 use _alpm_download_event_type_t::*;
@@ -54,6 +59,187 @@ bitflags! {
     }
 }
 
+#[derive(Debug, Eq, PartialEq, Clone, Ord, PartialOrd, Hash)]
+pub struct ParseSigLevelError {
+    directive: String,
+}
+
+impl ParseSigLevelError {
+    /// The directive that could not be parsed.
+    pub fn directive(&self) -> &str {
+        &self.directive
+    }
+}
+
+impl fmt::Display for ParseSigLevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid SigLevel directive '{}'", self.directive)
+    }
+}
+
+impl std::error::Error for ParseSigLevelError {}
+
+impl SigLevel {
+    /// Converts a siglevel returned by libalpm, keeping bits this crate does not know about.
+    ///
+    /// The getters return -1 on error, which is only possible with a null handle.
+    pub(crate) fn from_raw(level: c_int) -> SigLevel {
+        if level < 0 {
+            SigLevel::NONE
+        } else {
+            unsafe { SigLevel::from_bits_unchecked(level as u32) }
+        }
+    }
+
+    fn package_bits(self) -> SigLevel {
+        self & (SigLevel::PACKAGE
+            | SigLevel::PACKAGE_OPTIONAL
+            | SigLevel::PACKAGE_MARGINAL_OK
+            | SigLevel::PACKAGE_UNKNOWN_OK)
+    }
+
+    fn database_bits(self) -> SigLevel {
+        SigLevel::from_bits_truncate(
+            (self
+                & (SigLevel::DATABASE
+                    | SigLevel::DATABASE_OPTIONAL
+                    | SigLevel::DATABASE_MARGINAL_OK
+                    | SigLevel::DATABASE_UNKNOWN_OK))
+                .bits()
+                >> 10,
+        )
+    }
+
+    /// Parses pacman.conf SigLevel directives, as split by pacman.
+    ///
+    /// Directives are applied in order starting from [`SigLevel::NONE`], so later ones
+    /// override earlier ones. Directives without a `Package` or `Database` prefix apply to
+    /// both. `UseDefault` is not a pacman.conf directive, it sets [`SigLevel::USE_DEFAULT`]
+    /// so that the output of `to_string()` parses back to the same level.
+    pub fn from_conf_words<'s, I: IntoIterator<Item = &'s str>>(
+        words: I,
+    ) -> std::result::Result<SigLevel, ParseSigLevelError> {
+        let mut sig = SigLevel::NONE;
+
+        for word in words {
+            if word == "UseDefault" {
+                sig.insert(SigLevel::USE_DEFAULT);
+                continue;
+            }
+
+            let (pkg, db, directive) = if let Some(d) = word.strip_prefix("Package") {
+                (true, false, d)
+            } else if let Some(d) = word.strip_prefix("Database") {
+                (false, true, d)
+            } else {
+                (true, true, word)
+            };
+
+            let sides = [
+                (
+                    pkg,
+                    SigLevel::PACKAGE,
+                    SigLevel::PACKAGE_OPTIONAL,
+                    SigLevel::PACKAGE_MARGINAL_OK | SigLevel::PACKAGE_UNKNOWN_OK,
+                ),
+                (
+                    db,
+                    SigLevel::DATABASE,
+                    SigLevel::DATABASE_OPTIONAL,
+                    SigLevel::DATABASE_MARGINAL_OK | SigLevel::DATABASE_UNKNOWN_OK,
+                ),
+            ];
+
+            for &(enabled, check, optional, trust) in &sides {
+                if !enabled {
+                    continue;
+                }
+
+                match directive {
+                    "Never" => sig.remove(check),
+                    "Optional" => sig.insert(check | optional),
+                    "Required" => {
+                        sig.insert(check);
+                        sig.remove(optional);
+                    }
+                    "TrustedOnly" => sig.remove(trust),
+                    "TrustAll" => sig.insert(trust),
+                    _ => {
+                        return Err(ParseSigLevelError {
+                            directive: word.to_string(),
+                        })
+                    }
+                }
+            }
+        }
+
+        Ok(sig)
+    }
+
+    /// Renders the level as pacman.conf SigLevel directives. Same as `to_string()`.
+    pub fn to_conf_string(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// Parses a space separated list of pacman.conf SigLevel directives.
+///
+/// See [`SigLevel::from_conf_words`].
+impl FromStr for SigLevel {
+    type Err = ParseSigLevelError;
+
+    fn from_str(s: &str) -> std::result::Result<SigLevel, ParseSigLevelError> {
+        SigLevel::from_conf_words(s.split_whitespace())
+    }
+}
+
+/// Renders the level as pacman.conf SigLevel directives.
+///
+/// Directives that are the same for packages and databases are written without a prefix.
+/// [`SigLevel::USE_DEFAULT`] has no directive, in pacman.conf it is written by leaving out
+/// SigLevel. It is rendered as `UseDefault` on its own, as libalpm ignores the other bits
+/// when it is set.
+impl fmt::Display for SigLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.contains(SigLevel::USE_DEFAULT) {
+            return f.write_str("UseDefault");
+        }
+
+        fn check(sig: SigLevel) -> &'static str {
+            if !sig.contains(SigLevel::PACKAGE) {
+                "Never"
+            } else if sig.contains(SigLevel::PACKAGE_OPTIONAL) {
+                "Optional"
+            } else {
+                "Required"
+            }
+        }
+
+        fn trust(sig: SigLevel) -> &'static str {
+            if sig.contains(SigLevel::PACKAGE_MARGINAL_OK | SigLevel::PACKAGE_UNKNOWN_OK) {
+                "TrustAll"
+            } else {
+                "TrustedOnly"
+            }
+        }
+
+        let pkg = self.package_bits();
+        let db = self.database_bits();
+        let mut directives = Vec::new();
+
+        for &f in &[check, trust] {
+            if f(pkg) == f(db) {
+                directives.push(f(pkg).to_string());
+            } else {
+                directives.push(format!("Package{}", f(pkg)));
+                directives.push(format!("Database{}", f(db)));
+            }
+        }
+
+        f.write_str(&directives.join(" "))
+    }
+}
+
 bitflags! {
     pub struct Usage: u32 {
         const NONE = 0;
@@ -105,6 +291,15 @@ pub enum PackageReason {
     Depend = ALPM_PKG_REASON_DEPEND as u32,
 }
 
+impl fmt::Display for PackageReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackageReason::Explicit => f.write_str("explicit"),
+            PackageReason::Depend => f.write_str("dependency"),
+        }
+    }
+}
+
 bitflags! {
     pub struct PackageValidation: u32 {
         const UNKNOWN = ALPM_PKG_VALIDATION_UNKNOWN;
@@ -115,6 +310,62 @@ bitflags! {
     }
 }
 
+impl PackageValidation {
+    /// The package was validated with a PGP signature.
+    pub fn is_signature(&self) -> bool {
+        self.contains(PackageValidation::SIGNATURE)
+    }
+
+    /// The package was validated with a SHA-256 checksum.
+    pub fn is_sha256(&self) -> bool {
+        self.contains(PackageValidation::SHA256SUM)
+    }
+
+    /// The package was validated with an MD5 checksum.
+    pub fn is_md5(&self) -> bool {
+        self.contains(PackageValidation::MD5SUM)
+    }
+
+    /// The package was installed without any validation.
+    pub fn is_none(&self) -> bool {
+        self.contains(PackageValidation::NONE)
+    }
+
+    /// How the package was validated is not known.
+    pub fn is_unknown(&self) -> bool {
+        self.bits() == ALPM_PKG_VALIDATION_UNKNOWN
+    }
+
+    /// Describes the validation methods the way `pacman -Qi` does,
+    /// e.g. `"MD5 Sum  SHA-256 Sum"`, `"None"` or `"Unknown"`.
+    pub fn describe(&self) -> String {
+        let methods = [
+            (PackageValidation::MD5SUM, "MD5 Sum"),
+            (PackageValidation::SHA256SUM, "SHA-256 Sum"),
+            (PackageValidation::SIGNATURE, "Signature"),
+        ];
+        let methods = methods
+            .iter()
+            .filter(|(flag, _)| self.contains(*flag))
+            .map(|(_, name)| *name)
+            .collect::<Vec<_>>();
+
+        if !methods.is_empty() {
+            methods.join("  ")
+        } else if self.contains(PackageValidation::NONE) {
+            "None".into()
+        } else {
+            "Unknown".into()
+        }
+    }
+}
+
+impl fmt::Display for PackageValidation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.describe())
+    }
+}
+
 #[repr(u32)]
 #[derive(Debug, Eq, PartialEq, Copy, Clone, Ord, PartialOrd, Hash)]
 pub enum EventType {
@@ -997,6 +1248,7 @@ impl<'a> Group<'a> {
         unsafe { from_cstr((*self.inner).name) }
     }
 
+    /// The packages in this group, in the order of [`Db::pkgs`].
     pub fn packages(&self) -> AlpmList<'a, Package<'a>> {
         let pkgs = unsafe { (*self.inner).packages };
         AlpmList::from_parts(self.handle, pkgs)
@@ -1006,6 +1258,8 @@ impl<'a> Group<'a> {
 pub struct ChangeLog<'a> {
     pub(crate) pkg: &'a Pkg<'a>,
     pub(crate) stream: *mut c_void,
+    pub(crate) buf: Vec<u8>,
+    pub(crate) pos: usize,
 }
 
 impl<'a> fmt::Debug for ChangeLog<'a> {
@@ -1016,28 +1270,224 @@ impl<'a> fmt::Debug for ChangeLog<'a> {
 
 impl<'a> Drop for ChangeLog<'a> {
     fn drop(&mut self) {
-        unsafe { alpm_pkg_changelog_close(self.pkg.pkg, self.stream) };
+        if !self.stream.is_null() {
+            unsafe { alpm_pkg_changelog_close(self.pkg.pkg, self.stream) };
+        }
     }
 }
 
-impl<'a> Read for ChangeLog<'a> {
-    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        let ret = unsafe {
+impl<'a> ChangeLog<'a> {
+    /// Reopens the changelog so it can be read again from the start.
+    ///
+    /// If reopening fails the error is returned and the changelog reads as empty until
+    /// it is successfully reset.
+    pub fn reset(&mut self) -> Result<()> {
+        if !self.stream.is_null() {
+            unsafe { alpm_pkg_changelog_close(self.pkg.pkg, self.stream) };
+        }
+        self.buf.clear();
+        self.pos = 0;
+
+        self.stream = unsafe { alpm_pkg_changelog_open(self.pkg.pkg) };
+        self.pkg.handle.check_null(self.stream)
+    }
+
+    /// Reads the changelog line by line.
+    ///
+    /// Lines do not include the line ending. The last line is returned even if it does
+    /// not end in a newline.
+    pub fn lines(self) -> io::Lines<BufReader<ChangeLog<'a>>> {
+        BufReader::new(self).lines()
+    }
+
+    /// Reads at most `max` bytes from the changelog into a new buffer.
+    ///
+    /// Returns `None` once the end of the changelog has been reached. libalpm reports a
+    /// failed read the same way as the end, so a changelog that can't be read also returns
+    /// `None`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is 0.
+    pub fn read_chunk(&mut self, max: usize) -> Option<Vec<u8>> {
+        assert!(max != 0, "read_chunk max must be greater than 0");
+        let mut buf = vec![0; max];
+        let ret = self.read_buffered(&mut buf);
+
+        if ret == 0 {
+            return None;
+        }
+
+        buf.truncate(ret);
+        Some(buf)
+    }
+
+    /// Parses the changelog into entries.
+    ///
+    /// Entries are separated by blank lines and start with a `YYYY-MM-DD Author <email>`
+    /// header. Sections that do not start with a header are returned as
+    /// [`ChangeLogEntry::Raw`]. The changelog is closed when the iterator is dropped, even
+    /// if it was not read to the end.
+    pub fn entries(self) -> ChangeLogEntries<'a> {
+        ChangeLogEntries { changelog: self }
+    }
+
+    fn read_raw(&mut self, buf: &mut [u8]) -> usize {
+        if self.stream.is_null() {
+            return 0;
+        }
+
+        unsafe {
             alpm_pkg_changelog_read(
                 buf.as_mut_ptr() as *mut c_void,
                 buf.len(),
                 self.pkg.pkg,
                 self.stream,
             )
+        }
+    }
+
+    fn read_buffered(&mut self, buf: &mut [u8]) -> usize {
+        if self.pos == self.buf.len() {
+            return self.read_raw(buf);
+        }
+
+        let n = buf.len().min(self.buf.len() - self.pos);
+        buf[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.consume(n);
+        n
+    }
+}
+
+impl<'a> Read for ChangeLog<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        Ok(self.read_buffered(buf))
+    }
+}
+
+impl<'a> BufRead for ChangeLog<'a> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos == self.buf.len() {
+            let mut chunk = std::mem::take(&mut self.buf);
+            chunk.resize(8192, 0);
+            let n = self.read_raw(&mut chunk);
+            chunk.truncate(n);
+            self.buf = chunk;
+            self.pos = 0;
+        }
+
+        Ok(&self.buf[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.buf.len());
+    }
+}
+
+/// An entry of a package's changelog, see [`ChangeLog::entries`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ChangeLogEntry {
+    /// A section starting with a `YYYY-MM-DD Author <email>` header.
+    Entry {
+        date: String,
+        author: String,
+        /// The lines after the header, as they appear in the changelog.
+        body: Vec<String>,
+    },
+    /// A section without a valid header.
+    Raw(Vec<String>),
+}
+
+impl ChangeLogEntry {
+    fn parse(mut lines: Vec<String>) -> ChangeLogEntry {
+        let header = match parse_changelog_header(&lines[0]) {
+            Some(header) => header,
+            None => return ChangeLogEntry::Raw(lines),
         };
-        Ok(ret)
+        let (date, author) = (header.0.to_string(), header.1.to_string());
+        lines.remove(0);
+
+        ChangeLogEntry::Entry {
+            date,
+            author,
+            body: lines,
+        }
     }
 }
 
+fn parse_changelog_header(line: &str) -> Option<(&str, &str)> {
+    let date = line.get(..10)?;
+    let author = line.get(10..)?.strip_prefix(' ')?.trim();
+    let valid_date = date.bytes().enumerate().all(|(i, c)| match i {
+        4 | 7 => c == b'-',
+        _ => c.is_ascii_digit(),
+    });
+
+    if valid_date && !author.is_empty() {
+        Some((date, author))
+    } else {
+        None
+    }
+}
+
+/// Iterator over the entries of a changelog, see [`ChangeLog::entries`].
+#[derive(Debug)]
+pub struct ChangeLogEntries<'a> {
+    changelog: ChangeLog<'a>,
+}
+
+impl<'a> ChangeLogEntries<'a> {
+    fn next_line(&mut self) -> io::Result<Option<String>> {
+        let mut line = Vec::new();
+        if self.changelog.read_until(b'\n', &mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = String::from_utf8_lossy(&line);
+        Ok(Some(line.trim_end_matches(&['\n', '\r'][..]).to_string()))
+    }
+}
+
+impl<'a> Iterator for ChangeLogEntries<'a> {
+    type Item = io::Result<ChangeLogEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut lines = Vec::new();
+
+        loop {
+            match self.next_line() {
+                Ok(Some(line)) if line.trim().is_empty() => {
+                    if !lines.is_empty() {
+                        break;
+                    }
+                }
+                Ok(Some(line)) => lines.push(line),
+                Ok(None) => break,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+
+        if lines.is_empty() {
+            None
+        } else {
+            Some(Ok(ChangeLogEntry::parse(lines)))
+        }
+    }
+}
+
+impl<'a> std::iter::FusedIterator for ChangeLogEntries<'a> {}
+
+/// The result of matching a path against the NoUpgrade or NoExtract patterns.
+///
+/// Patterns are checked from last to first and the first pattern that matches decides the
+/// result, like in pacman.
 #[derive(Debug, Eq, PartialEq, Copy, Clone, Ord, PartialOrd, Hash)]
 pub enum Match {
+    /// No pattern matched.
     No,
+    /// A pattern matched, so the path is covered by the option.
     Yes,
+    /// A negated pattern such as `!etc/foo` matched, so the path is explicitly not covered
+    /// by the option. pacman treats this the same as [`Match::No`].
     Inverted,
 }
 
@@ -1079,6 +1529,36 @@ impl Backup {
     pub fn name(&self) -> &str {
         unsafe { from_cstr((*self.inner).name) }
     }
+
+    /// Checks whether the file at `root` + [`name`](Backup::name) differs from the
+    /// hash recorded when the package was installed.
+    pub fn is_modified<P: AsRef<Path>>(&self, root: P) -> io::Result<bool> {
+        let path = root.as_ref().join(self.name());
+        fs::File::open(&path)?;
+        let md5 = compute_md5sum(path.into_os_string().into_vec()).map_err(io::Error::other)?;
+        Ok(md5 != self.hash())
+    }
+
+    /// Checks the file at `root` + [`name`](Backup::name) against the recorded hash, like
+    /// `pacman -Qii` does. Same as [`is_modified`](Backup::is_modified) with the errors
+    /// turned into a status.
+    pub fn status<P: AsRef<Path>>(&self, root: P) -> BackupStatus {
+        match self.is_modified(root) {
+            Ok(false) => BackupStatus::Unmodified,
+            Ok(true) => BackupStatus::Modified,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => BackupStatus::Missing,
+            Err(_) => BackupStatus::Unreadable,
+        }
+    }
+}
+
+/// The state of a backup file on disk, see [`Backup::status`].
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Ord, PartialOrd, Hash)]
+pub enum BackupStatus {
+    Unmodified,
+    Modified,
+    Missing,
+    Unreadable,
 }
 
 pub struct AnyDownloadEvent<'a> {
@@ -1203,14 +1683,50 @@ pub struct Signature {
 }
 
 impl Signature {
+    /// Decodes a base64 encoded signature, such as [`Pkg::base64_sig`].
+    pub fn decode<S: Into<Vec<u8>>>(
+        b64: S,
+    ) -> std::result::Result<Signature, SignatureDecodeError> {
+        let b64 = CString::new(b64).map_err(|_| SignatureDecodeError)?;
+        let mut sig = ptr::null_mut();
+        let mut len = 0;
+        let ret = unsafe { alpm_decode_signature(b64.as_ptr(), &mut sig, &mut len) };
+        if ret != 0 {
+            return Err(SignatureDecodeError);
+        }
+
+        Ok(Signature { sig, len })
+    }
+
     pub fn sig(&self) -> &[u8] {
-        unsafe { slice::from_raw_parts(self.sig, self.len) }
+        self.as_bytes()
+    }
+
+    /// The raw signature. Empty if there is no signature data.
+    pub fn as_bytes(&self) -> &[u8] {
+        if self.sig.is_null() {
+            &[]
+        } else {
+            unsafe { slice::from_raw_parts(self.sig, self.len) }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.as_bytes().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
     }
 }
 
 impl fmt::Debug for Signature {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_list().entries(self.sig()).finish()
+        f.write_str("Signature(")?;
+        for b in self.as_bytes() {
+            write!(f, "{:02x}", b)?;
+        }
+        f.write_str(")")
     }
 }
 
@@ -1218,7 +1734,13 @@ impl Deref for Signature {
     type Target = [u8];
 
     fn deref(&self) -> &Self::Target {
-        self.sig()
+        self.as_bytes()
+    }
+}
+
+impl AsRef<[u8]> for Signature {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
     }
 }
 
@@ -1227,3 +1749,226 @@ impl Drop for Signature {
         unsafe { crate::free(self.sig as _) }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validation_describe() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let pkg = handle.localdb().pkg("linux").unwrap();
+        assert_eq!(pkg.validation().to_string(), "SHA-256 Sum");
+
+        assert_eq!(PackageValidation::UNKNOWN.describe(), "Unknown");
+        assert_eq!(PackageValidation::NONE.describe(), "None");
+
+        let all = PackageValidation::MD5SUM.bits()
+            | PackageValidation::SHA256SUM.bits()
+            | PackageValidation::SIGNATURE.bits()
+            | 1 << 10;
+        let v = unsafe { PackageValidation::from_bits_unchecked(all) };
+        assert_eq!(v.bits(), all);
+        assert_eq!(v.describe(), "MD5 Sum  SHA-256 Sum  Signature");
+
+        let v = unsafe { PackageValidation::from_bits_unchecked(1 << 10) };
+        assert_eq!(v.describe(), "Unknown");
+    }
+
+    #[test]
+    fn test_validation_reason() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let db = handle.localdb();
+
+        let autoconf = db.pkg("autoconf").unwrap();
+        let v = autoconf.validation();
+        assert!(v.is_signature());
+        assert!(!v.is_sha256() && !v.is_md5() && !v.is_none() && !v.is_unknown());
+        assert_eq!(autoconf.reason(), PackageReason::Explicit);
+        assert_eq!(autoconf.reason().to_string(), "explicit");
+
+        let bash = db.pkg("bash").unwrap();
+        assert!(bash.validation().is_signature());
+        assert_eq!(bash.reason().to_string(), "dependency");
+
+        let filesystem = db.pkg("filesystem").unwrap();
+        assert!(filesystem.validation().is_sha256());
+        assert!(!filesystem.validation().is_signature());
+
+        let expac = db.pkg("expac-git").unwrap();
+        assert!(expac.validation().is_none());
+        assert!(!expac.validation().is_unknown());
+        assert_eq!(expac.validation().to_string(), "None");
+
+        assert!(PackageValidation::UNKNOWN.is_unknown());
+        assert!(!PackageValidation::UNKNOWN.is_none());
+        assert!(PackageValidation::MD5SUM.is_md5());
+    }
+
+    #[test]
+    fn test_changelog_raw_entries() {
+        let lines = |s: &[&str]| s.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+
+        let raw = ChangeLogEntry::parse(lines(&["not a header", "\t* body"]));
+        assert_eq!(
+            raw,
+            ChangeLogEntry::Raw(lines(&["not a header", "\t* body"]))
+        );
+        let raw = ChangeLogEntry::parse(lines(&["2018-1x-12 Someone"]));
+        assert!(matches!(raw, ChangeLogEntry::Raw(_)));
+        let raw = ChangeLogEntry::parse(lines(&["2018-11-12"]));
+        assert!(matches!(raw, ChangeLogEntry::Raw(_)));
+
+        let entry = ChangeLogEntry::parse(lines(&["2018-11-12  Someone <a@b>  "]));
+        assert_eq!(
+            entry,
+            ChangeLogEntry::Entry {
+                date: "2018-11-12".into(),
+                author: "Someone <a@b>".into(),
+                body: Vec::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_backup_is_modified() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let pkg = handle.localdb().pkg("bash").unwrap();
+        let backup = pkg
+            .backup()
+            .iter()
+            .find(|b| b.name() == "etc/bash.bashrc")
+            .unwrap();
+
+        let root = TempDir::new("backup");
+        let err = backup.is_modified(&root).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+
+        fs::create_dir_all(root.join("etc")).unwrap();
+        fs::write(root.join("etc/bash.bashrc"), "PS1='$ '\n").unwrap();
+        assert!(backup.is_modified(&root).unwrap());
+        assert_eq!(backup.status(&root), BackupStatus::Modified);
+        fs::remove_file(root.join("etc/bash.bashrc")).unwrap();
+        assert_eq!(backup.status(&root), BackupStatus::Missing);
+    }
+
+    #[test]
+    fn test_siglevel_parse() {
+        let sig = "Required DatabaseOptional".parse::<SigLevel>().unwrap();
+        assert_eq!(
+            sig,
+            SigLevel::PACKAGE | SigLevel::DATABASE | SigLevel::DATABASE_OPTIONAL
+        );
+        assert_eq!(
+            sig.to_string(),
+            "PackageRequired DatabaseOptional TrustedOnly"
+        );
+        assert_eq!(sig.to_string().parse::<SigLevel>().unwrap(), sig);
+
+        let sig = "Optional TrustAll PackageNever"
+            .parse::<SigLevel>()
+            .unwrap();
+        assert_eq!(
+            sig,
+            SigLevel::PACKAGE_OPTIONAL
+                | SigLevel::PACKAGE_MARGINAL_OK
+                | SigLevel::PACKAGE_UNKNOWN_OK
+                | SigLevel::DATABASE
+                | SigLevel::DATABASE_OPTIONAL
+                | SigLevel::DATABASE_MARGINAL_OK
+                | SigLevel::DATABASE_UNKNOWN_OK
+        );
+        let s = sig.to_string();
+        assert_eq!(s, "PackageNever DatabaseOptional TrustAll");
+        assert_eq!(s.parse::<SigLevel>().unwrap().to_string(), s);
+
+        assert_eq!("".parse::<SigLevel>().unwrap(), SigLevel::NONE);
+        assert_eq!(SigLevel::NONE.to_string(), "Never TrustedOnly");
+        assert_eq!(SigLevel::USE_DEFAULT.to_string(), "UseDefault");
+        assert_eq!(SigLevel::USE_DEFAULT.to_conf_string(), "UseDefault");
+        assert_eq!(
+            "UseDefault".parse::<SigLevel>().unwrap(),
+            SigLevel::USE_DEFAULT
+        );
+        assert_eq!(
+            (SigLevel::USE_DEFAULT | SigLevel::PACKAGE).to_string(),
+            "UseDefault"
+        );
+        let err = "Required Sometimes".parse::<SigLevel>().unwrap_err();
+        assert_eq!(err.directive(), "Sometimes");
+        assert_eq!(err.to_string(), "Invalid SigLevel directive 'Sometimes'");
+        assert_eq!(
+            "PackageTrustOnly"
+                .parse::<SigLevel>()
+                .unwrap_err()
+                .directive(),
+            "PackageTrustOnly"
+        );
+        assert_eq!(
+            SigLevel::from_conf_words(vec!["Package"])
+                .unwrap_err()
+                .directive(),
+            "Package"
+        );
+    }
+
+    #[test]
+    fn test_siglevel_conf_words() {
+        let sig = SigLevel::from_conf_words(vec!["PackageNever", "PackageTrustAll"]).unwrap();
+        assert_eq!(
+            sig,
+            SigLevel::PACKAGE_MARGINAL_OK | SigLevel::PACKAGE_UNKNOWN_OK
+        );
+        assert_eq!(
+            sig.to_conf_string(),
+            "Never PackageTrustAll DatabaseTrustedOnly"
+        );
+
+        // Later directives override earlier ones.
+        let sig = SigLevel::from_conf_words(vec!["Optional", "PackageRequired", "DatabaseNever"])
+            .unwrap();
+        assert_eq!(sig, SigLevel::PACKAGE | SigLevel::DATABASE_OPTIONAL);
+    }
+
+    #[test]
+    fn test_signature() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        let db = handle
+            .register_syncdb("core", crate::SigLevel::NONE)
+            .unwrap();
+        let pkg = db.pkg("linux").unwrap();
+        let b64 = pkg.base64_sig().unwrap();
+
+        let decoded = Signature::decode(b64).unwrap();
+        let sig = pkg.sig().unwrap();
+        assert!(!sig.is_empty());
+        assert_eq!(decoded.len(), sig.len());
+        assert_eq!(decoded.as_ref(), sig.as_bytes());
+        assert_eq!(crate::decode_signature(b64).unwrap(), sig.as_bytes());
+        assert!(format!("{:?}", sig).starts_with("Signature(8901"));
+
+        assert!(Signature::decode("!!!").is_err());
+        assert!(Signature::decode("iQ\0IzBAAB").is_err());
+
+        let empty = Signature {
+            sig: ptr::null_mut(),
+            len: 0,
+        };
+        assert!(empty.is_empty());
+        assert_eq!(empty.as_bytes(), &[] as &[u8]);
+    }
+
+    #[test]
+    fn test_siglevel_from_raw() {
+        let raw = (1 << 29) | SigLevel::PACKAGE.bits();
+        let level = SigLevel::from_raw(raw as c_int);
+        assert!(level.contains(SigLevel::PACKAGE));
+        assert_eq!(level.bits(), raw);
+        let _ = level.to_string();
+        let _ = format!("{:?}", level);
+
+        let level = SigLevel::from_raw(SigLevel::USE_DEFAULT.bits() as c_int);
+        assert_eq!(level, SigLevel::USE_DEFAULT);
+        assert_eq!(SigLevel::from_raw(-1), SigLevel::NONE);
+    }
+}
@@ -1,4 +1,6 @@
-use std::ffi::{CStr, CString};
+use crate::free;
+
+use std::ffi::{c_void, CStr, CString};
 use std::fmt;
 
 use alpm_sys::*;
@@ -15,23 +17,25 @@ impl fmt::Display for ChecksumError {
 impl std::error::Error for ChecksumError {}
 
 pub fn compute_md5sum<S: Into<Vec<u8>>>(s: S) -> Result<String, ChecksumError> {
-    let s = CString::new(s).unwrap();
+    let s = CString::new(s).map_err(|_| ChecksumError)?;
     let ret = unsafe { alpm_compute_md5sum(s.as_ptr()) };
     if ret.is_null() {
         return Err(ChecksumError);
     }
 
-    let s = unsafe { CStr::from_ptr(ret).to_str().unwrap() };
-    Ok(s.into())
+    let s = unsafe { CStr::from_ptr(ret).to_str().unwrap().to_string() };
+    unsafe { free(ret as *mut c_void) };
+    Ok(s)
 }
 
 pub fn compute_sha256sum<S: Into<Vec<u8>>>(s: S) -> Result<String, ChecksumError> {
-    let s = CString::new(s).unwrap();
+    let s = CString::new(s).map_err(|_| ChecksumError)?;
     let ret = unsafe { alpm_compute_sha256sum(s.as_ptr()) };
     if ret.is_null() {
         return Err(ChecksumError);
     }
 
-    let s = unsafe { CStr::from_ptr(ret).to_str().unwrap() };
-    Ok(s.into())
+    let s = unsafe { CStr::from_ptr(ret).to_str().unwrap().to_string() };
+    unsafe { free(ret as *mut c_void) };
+    Ok(s)
 }
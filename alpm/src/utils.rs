@@ -1,16 +1,121 @@
-use std::ffi::CStr;
+use std::ffi::{CStr, CString};
+use std::fmt;
 use std::os::raw::c_char;
 
+/// Borrows a string owned by libalpm.
+///
+/// libalpm does not validate encodings, so if the string is not valid UTF-8 only the valid
+/// prefix is returned. The `*_bytes` accessors give the raw data.
 pub unsafe fn from_cstr<'a>(s: *const c_char) -> &'a str {
     debug_assert!(!s.is_null(), "str is null");
-    CStr::from_ptr(s).to_str().unwrap()
+    valid_prefix(CStr::from_ptr(s))
 }
 
 pub unsafe fn from_cstr_optional<'a>(s: *const c_char) -> Option<&'a str> {
-    s.as_ref().map(|s| CStr::from_ptr(s).to_str().unwrap())
+    s.as_ref().map(|s| valid_prefix(CStr::from_ptr(s)))
+}
+
+pub unsafe fn from_cstr_bytes_optional<'a>(s: *const c_char) -> Option<&'a [u8]> {
+    s.as_ref().map(|s| CStr::from_ptr(s).to_bytes())
+}
+
+pub(crate) fn valid_prefix(s: &CStr) -> &str {
+    match s.to_str() {
+        Ok(s) => s,
+        Err(e) => std::str::from_utf8(&s.to_bytes()[..e.valid_up_to()]).unwrap(),
+    }
 }
 
 // temp function for functions that should return Option<str>
 pub unsafe fn from_cstr_optional2<'a>(s: *const c_char) -> &'a str {
     from_cstr_optional(s).unwrap_or("")
 }
+
+pub fn cstring<S: Into<Vec<u8>>>(s: S) -> crate::Result<CString> {
+    CString::new(s).map_err(|_| crate::Error::WrongArgs)
+}
+
+/// Debug formats the items of an iterator as a list, stopping after the first few.
+pub(crate) struct DebugTruncated<I>(pub(crate) I);
+
+impl<I> fmt::Debug for DebugTruncated<I>
+where
+    I: Iterator + Clone,
+    I::Item: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const MAX: usize = 10;
+        let mut iter = self.0.clone();
+        let mut list = f.debug_list();
+        list.entries(iter.by_ref().take(MAX));
+        let rest = iter.count();
+        if rest != 0 {
+            list.entry(&format_args!("... {} more", rest));
+        }
+        list.finish()
+    }
+}
+
+/// A directory for a test to write to, removed again when dropped.
+///
+/// Tests run in parallel so every test needs its own `name`. Anything left over from an
+/// earlier run that did not clean up is removed first.
+#[cfg(test)]
+pub(crate) struct TempDir(std::path::PathBuf);
+
+#[cfg(test)]
+impl TempDir {
+    pub(crate) fn new(name: &str) -> TempDir {
+        let path = std::env::temp_dir().join(format!("alpm-rs-test-{}", name));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+
+    /// Creates a database path at `db` containing a local database with copies of the
+    /// given packages from `tests/db/local`, such as `"vifm-0.10-1"`.
+    pub(crate) fn local_db(&self, pkgs: &[&str]) -> std::path::PathBuf {
+        let dbpath = self.join("db");
+        let local = dbpath.join("local");
+        std::fs::create_dir_all(&local).unwrap();
+        std::fs::copy(
+            "tests/db/local/ALPM_DB_VERSION",
+            local.join("ALPM_DB_VERSION"),
+        )
+        .unwrap();
+
+        for pkg in pkgs {
+            let src = std::path::Path::new("tests/db/local").join(pkg);
+            std::fs::create_dir_all(local.join(pkg)).unwrap();
+            for file in std::fs::read_dir(&src).unwrap() {
+                let file = file.unwrap();
+                std::fs::copy(file.path(), local.join(pkg).join(file.file_name())).unwrap();
+            }
+        }
+
+        dbpath
+    }
+}
+
+#[cfg(test)]
+impl std::ops::Deref for TempDir {
+    type Target = std::path::Path;
+
+    fn deref(&self) -> &std::path::Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl AsRef<std::path::Path> for TempDir {
+    fn as_ref(&self) -> &std::path::Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
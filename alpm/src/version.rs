@@ -4,15 +4,30 @@ use std::ffi::CString;
 use std::fmt;
 use std::ops::Deref;
 use std::os::raw::c_char;
+use std::str::FromStr;
+
+use crate::utils::{cstring, valid_prefix};
+use crate::{Error, Result};
 
 use alpm_sys::*;
 
+/// Compares two version strings the same way pacman does.
+///
+/// The strings are compared up to the first nul byte, which is all libalpm would see.
 pub fn vercmp<S: Into<Vec<u8>>>(a: S, b: S) -> Ordering {
-    let a = Version::new(a);
-    let b = Version::new(b);
+    let a = truncate_nul(a);
+    let b = truncate_nul(b);
     a.vercmp(b)
 }
 
+fn truncate_nul<S: Into<Vec<u8>>>(s: S) -> Version {
+    let mut s = s.into();
+    if let Some(nul) = s.iter().position(|&b| b == 0) {
+        s.truncate(nul);
+    }
+    Version::new(s)
+}
+
 #[repr(transparent)]
 #[derive(Debug, Eq)]
 pub struct Ver(CStr);
@@ -22,10 +37,17 @@ impl Ver {
         unsafe { &*(s as *const CStr as *const Ver) }
     }
 
+    /// The version as a string. libalpm does not validate encodings, so if the version is
+    /// not valid UTF-8 only the valid prefix is returned.
     pub fn as_str(&self) -> &str {
         self
     }
 
+    /// The version as raw bytes, without the nul terminator.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.0.to_bytes()
+    }
+
     pub fn vercmp<V: AsRef<Ver>>(&self, other: V) -> Ordering {
         unsafe { alpm_pkg_vercmp(self.0.as_ptr(), other.as_ref().0.as_ptr()).cmp(&0) }
     }
@@ -50,7 +72,7 @@ impl AsRef<Ver> for Ver {
 impl Deref for Ver {
     type Target = str;
     fn deref(&self) -> &Self::Target {
-        self.0.to_str().unwrap()
+        valid_prefix(&self.0)
     }
 }
 
@@ -62,7 +84,19 @@ impl fmt::Display for Ver {
 
 impl PartialOrd for Ver {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        unsafe { alpm_pkg_vercmp(self.0.as_ptr(), other.0.as_ptr()).partial_cmp(&0) }
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ver {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.vercmp(other)
+    }
+}
+
+impl PartialOrd<str> for Ver {
+    fn partial_cmp(&self, other: &str) -> Option<Ordering> {
+        Some(vercmp(self.as_bytes(), other.as_bytes()))
     }
 }
 
@@ -94,9 +128,16 @@ impl PartialEq<Version> for &Ver {
 pub struct Version(CString);
 
 impl Version {
+    /// Panics if the string contains a nul byte, use [`try_new`](Version::try_new) for
+    /// strings that come from the user.
     pub fn new<S: Into<Vec<u8>>>(s: S) -> Self {
-        let s = CString::new(s).unwrap();
-        Version(s)
+        Version::try_new(s).unwrap()
+    }
+
+    /// Fails with [`Error::WrongArgs`] if the string contains a nul byte.
+    pub fn try_new<S: Into<Vec<u8>>>(s: S) -> Result<Self> {
+        let s = cstring(s)?;
+        Ok(Version(s))
     }
 
     pub fn as_ver(&self) -> &Ver {
@@ -104,6 +145,15 @@ impl Version {
     }
 }
 
+/// See [`Version::try_new`].
+impl FromStr for Version {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Version> {
+        Version::try_new(s)
+    }
+}
+
 impl fmt::Display for Version {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         fmt.write_str(self.as_ver())
@@ -117,9 +167,21 @@ impl Deref for Version {
     }
 }
 
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.vercmp(other)
+    }
+}
+
+impl PartialOrd<&str> for Version {
+    fn partial_cmp(&self, other: &&str) -> Option<Ordering> {
+        Some(vercmp(self.as_str(), other))
+    }
+}
+
 impl PartialOrd for Version {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        unsafe { alpm_pkg_vercmp(self.0.as_ptr(), other.0.as_ptr()).partial_cmp(&0) }
+        Some(self.cmp(other))
     }
 }
 
@@ -149,49 +211,49 @@ impl PartialEq<&Ver> for Version {
 
 impl PartialEq<str> for Ver {
     fn eq(&self, other: &str) -> bool {
-        self.as_str().eq(other)
+        vercmp(self.as_str(), other) == Ordering::Equal
     }
 }
 
 impl PartialEq<String> for Ver {
     fn eq(&self, other: &String) -> bool {
-        self.as_str().eq(other)
+        vercmp(self.as_str(), other) == Ordering::Equal
     }
 }
 
 impl PartialEq<&str> for Version {
     fn eq(&self, other: &&str) -> bool {
-        self.as_str() == *other
+        vercmp(self.as_str(), other) == Ordering::Equal
     }
 }
 
 impl PartialEq<String> for Version {
     fn eq(&self, other: &String) -> bool {
-        self.as_str().eq(other)
+        vercmp(self.as_str(), other) == Ordering::Equal
     }
 }
 
 impl PartialEq<Ver> for str {
     fn eq(&self, other: &Ver) -> bool {
-        self == other.as_str()
+        vercmp(self, other.as_str()) == Ordering::Equal
     }
 }
 
 impl PartialEq<&Ver> for String {
     fn eq(&self, other: &&Ver) -> bool {
-        self == other.as_str()
+        vercmp(self.as_str(), other.as_str()) == Ordering::Equal
     }
 }
 
 impl PartialEq<Version> for &str {
     fn eq(&self, other: &Version) -> bool {
-        *self == other.as_str()
+        vercmp(*self, other.as_str()) == Ordering::Equal
     }
 }
 
 impl PartialEq<Version> for String {
     fn eq(&self, other: &Version) -> bool {
-        self == other.as_str()
+        vercmp(self.as_str(), other.as_str()) == Ordering::Equal
     }
 }
 
@@ -227,5 +289,117 @@ mod tests {
         assert!(dep2.version().unwrap() == Version::new("34"));
         assert!(dep2.version().unwrap() >= Version::new("34"));
         assert!(Version::new("1.9.3-2") < Version::new("1.10.2-1"));
+
+        assert_eq!(Version::try_new("1.0").unwrap(), Version::new("1.0"));
+        assert_eq!("1.0".parse::<Version>().unwrap(), Version::new("1.0"));
+        assert_eq!(Version::try_new("1\02").unwrap_err(), Error::WrongArgs);
+        assert_eq!("1\02".parse::<Version>().unwrap_err(), Error::WrongArgs);
+        assert_eq!(vercmp("1\02", "1"), Ordering::Equal);
+    }
+
+    // from pacman's test/util/vercmptest.sh
+    const VERCMP_TESTS: &[(&str, &str, i8)] = &[
+        // all similar length, no pkgrel
+        ("1.5.0", "1.5.0", 0),
+        ("1.5.1", "1.5.0", 1),
+        // mixed length
+        ("1.5.1", "1.5", 1),
+        // with pkgrel, simple
+        ("1.5.0-1", "1.5.0-1", 0),
+        ("1.5.0-1", "1.5.0-2", -1),
+        ("1.5.0-1", "1.5.1-1", -1),
+        ("1.5.0-2", "1.5.1-1", -1),
+        // with pkgrel, mixed lengths
+        ("1.5-1", "1.5.1-1", -1),
+        ("1.5-2", "1.5.1-1", -1),
+        ("1.5-2", "1.5.1-2", -1),
+        // mixed pkgrel inclusion
+        ("1.5", "1.5-1", 0),
+        ("1.5-1", "1.5", 0),
+        ("1.1-1", "1.1", 0),
+        ("1.0-1", "1.1", -1),
+        ("1.1-1", "1.0", 1),
+        // alphanumeric versions
+        ("1.5b-1", "1.5-1", -1),
+        ("1.5b", "1.5", -1),
+        ("1.5b-1", "1.5", -1),
+        ("1.5b", "1.5.1", -1),
+        // from the manpage
+        ("1.0a", "1.0alpha", -1),
+        ("1.0alpha", "1.0b", -1),
+        ("1.0b", "1.0beta", -1),
+        ("1.0beta", "1.0rc", -1),
+        ("1.0rc", "1.0", -1),
+        // going crazy? alpha-dotted versions
+        ("1.5.a", "1.5", 1),
+        ("1.5.b", "1.5.a", 1),
+        ("1.5.1", "1.5.b", 1),
+        // alpha dots and dashes
+        ("1.5.b-1", "1.5.b", 0),
+        ("1.5-1", "1.5.b", -1),
+        // same/similar content, differing separators
+        ("2.0", "2_0", 0),
+        ("2.0_a", "2_0.a", 0),
+        ("2.0a", "2.0.a", -1),
+        ("2___a", "2_a", 1),
+        // epoch included version comparisons
+        ("0:1.0", "0:1.0", 0),
+        ("0:1.0", "0:1.1", -1),
+        ("1:1.0", "0:1.0", 1),
+        ("1:1.0", "0:1.1", 1),
+        ("1:1.0", "2:1.1", -1),
+        // epoch + sometimes present pkgrel
+        ("1:1.0", "0:1.0-1", 1),
+        ("1:1.0-1", "0:1.1-1", 1),
+        // epoch included on one version
+        ("0:1.0", "1.0", 0),
+        ("0:1.0", "1.1", -1),
+        ("0:1.1", "1.0", 1),
+        ("1:1.0", "1.0", 1),
+        ("1:1.0", "1.1", 1),
+        ("1:1.1", "1.1", 1),
+    ];
+
+    #[test]
+    fn test_vercmp_table() {
+        for &(a, b, expected) in VERCMP_TESTS {
+            let expected = expected.cmp(&0);
+            assert_eq!(vercmp(a, b), expected, "{} {}", a, b);
+            assert_eq!(vercmp(b, a), expected.reverse(), "{} {}", b, a);
+            assert_eq!(Version::new(a).cmp(&Version::new(b)), expected);
+            assert_eq!(Version::new(a).partial_cmp(&b), Some(expected));
+        }
+    }
+
+    #[test]
+    fn test_ver_ord_str() {
+        let handle = crate::Alpm::new("/", "tests/db").unwrap();
+        let pkg = handle.localdb().pkg("linux").unwrap();
+        let version = pkg.version();
+
+        assert!(version > "4.19");
+        assert!(version < "5.10.1");
+        assert!(version == version.as_str());
+        assert!(*version < *"1:0");
+        assert!("1.0" == Version::new("1.0-1"));
+
+        let mut versions = [
+            Version::new("1.0"),
+            Version::new("1:0.1"),
+            Version::new("1.0rc1"),
+            Version::new("1.0.1"),
+        ];
+        versions.sort();
+        let versions = versions.iter().map(|v| v.as_str()).collect::<Vec<_>>();
+        assert_eq!(versions, ["1.0rc1", "1.0", "1.0.1", "1:0.1"]);
+    }
+
+    #[test]
+    fn test_ver_non_utf8() {
+        let version = Version::new(&b"1.0\xe9-1"[..]);
+        assert_eq!(version.as_bytes(), b"1.0\xe9-1");
+        assert_eq!(version.as_str(), "1.0");
+        assert_eq!(version.to_string(), "1.0");
+        assert!(version.as_ver() > "0.9");
     }
 }
@@ -0,0 +1,268 @@
+use crate::{Alpm, Error, FetchResult, Result};
+
+use std::fs;
+use std::path::Path;
+use std::process::{Child, Command, ExitStatus};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// An external download command in the style of pacman's `XferCommand`.
+///
+/// `%u` is replaced with the url and `%o` with the path of a temporary `.part` file which
+/// is renamed into place once the command succeeds. When `%o` is not used the command is
+/// expected to save the file into the current directory, which is set to the download
+/// directory. Arguments are split on whitespace and may be quoted with `'` or `"`. The
+/// command is run directly, not through a shell.
+///
+/// The command inherits stdout and stderr, and is in the same process group as the caller
+/// so it receives SIGINT from the terminal. A command that exits unsuccessfully, is killed
+/// by a signal or exceeds the timeout is reported as [`FetchResult::Err`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XferCommand {
+    args: Vec<String>,
+    timeout: Option<Duration>,
+}
+
+impl XferCommand {
+    /// Parses an `XferCommand` template.
+    ///
+    /// Fails with [`Error::WrongArgs`] if the template is empty, contains an unterminated
+    /// quote or does not contain `%u`.
+    pub fn new(template: &str) -> Result<XferCommand> {
+        let args = split_words(template).ok_or(Error::WrongArgs)?;
+
+        if args.is_empty() || !args.iter().any(|arg| arg.contains("%u")) {
+            return Err(Error::WrongArgs);
+        }
+
+        Ok(XferCommand {
+            args,
+            timeout: None,
+        })
+    }
+
+    /// Kills the command if it has not finished after `timeout`.
+    pub fn timeout(mut self, timeout: Duration) -> XferCommand {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    /// Downloads `url` into the directory `localpath`.
+    ///
+    /// This has the same signature as the callback passed to
+    /// [`set_fetch_cb`](Alpm::set_fetch_cb).
+    pub fn fetch(&self, url: &str, localpath: &str, force: bool) -> FetchResult {
+        let filename = url.rsplit('/').next().unwrap_or(url);
+        if filename.is_empty() {
+            return FetchResult::Err;
+        }
+
+        let localpath = Path::new(localpath);
+        let destfile = localpath.join(filename);
+        let tempfile = localpath.join(format!("{}.part", filename));
+
+        if force {
+            let _ = fs::remove_file(&destfile);
+            let _ = fs::remove_file(&tempfile);
+        }
+
+        let tempfile_str = match tempfile.to_str() {
+            Some(s) => s,
+            None => return FetchResult::Err,
+        };
+
+        let mut usepart = false;
+        let args = self
+            .args
+            .iter()
+            .map(|arg| {
+                usepart |= arg.contains("%o");
+                arg.replace("%o", tempfile_str).replace("%u", url)
+            })
+            .collect::<Vec<_>>();
+
+        let child = Command::new(&args[0])
+            .args(&args[1..])
+            .current_dir(localpath)
+            .spawn();
+
+        let status = match child.and_then(|child| self.wait(child)) {
+            Ok(Some(status)) => status,
+            _ => return FetchResult::Err,
+        };
+
+        if !status.success() {
+            return FetchResult::Err;
+        }
+
+        if usepart && fs::rename(&tempfile, &destfile).is_err() {
+            return FetchResult::Err;
+        }
+
+        FetchResult::Ok
+    }
+
+    fn wait(&self, mut child: Child) -> std::io::Result<Option<ExitStatus>> {
+        let timeout = match self.timeout {
+            Some(timeout) => timeout,
+            None => return child.wait().map(Some),
+        };
+
+        let start = Instant::now();
+
+        loop {
+            if let Some(status) = child.try_wait()? {
+                return Ok(Some(status));
+            }
+
+            if start.elapsed() >= timeout {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Ok(None);
+            }
+
+            sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+impl Alpm {
+    /// Downloads files using an external command instead of libalpm's downloader.
+    ///
+    /// This replaces the fetch callback. See [`XferCommand`] for the template format.
+    pub fn set_xfer_command(&self, template: &str) -> Result<()> {
+        let cmd = XferCommand::new(template)?;
+        self.set_fetch_cb(cmd, |url, localpath, force, cmd| {
+            cmd.fetch(url, localpath, force)
+        });
+        Ok(())
+    }
+}
+
+fn split_words(s: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') => word.push(chars.next()?),
+            (Some(_), c) => word.push(c),
+            (None, '\'') | (None, '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, '\\') => {
+                word.push(chars.next()?);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+
+    if quote.is_some() {
+        return None;
+    }
+    if in_word {
+        words.push(word);
+    }
+
+    Some(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::TempDir;
+
+    const CP: &str = r#"sh -c 'cp "${1#file://}" "$2"' sh %u %o"#;
+
+    fn setup(name: &str) -> (TempDir, String) {
+        let dir = TempDir::new(name);
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::create_dir_all(dir.join("dest")).unwrap();
+        fs::write(dir.join("src/foo.db"), "foo").unwrap();
+        let url = format!("file://{}", dir.join("src/foo.db").display());
+        (dir, url)
+    }
+
+    #[test]
+    fn test_parse() {
+        let cmd = XferCommand::new(r#"curl -L -C - -f -o %o "%u" 'a b' c\ d"#).unwrap();
+        assert_eq!(
+            cmd.args(),
+            ["curl", "-L", "-C", "-", "-f", "-o", "%o", "%u", "a b", "c d"]
+        );
+
+        assert_eq!(XferCommand::new(""), Err(Error::WrongArgs));
+        assert_eq!(XferCommand::new("curl -o %o"), Err(Error::WrongArgs));
+        assert_eq!(XferCommand::new("curl 'a %u"), Err(Error::WrongArgs));
+    }
+
+    #[test]
+    fn test_fetch() {
+        let (dir, url) = setup("xfer-fetch");
+        let dest = dir.join("dest");
+        let cmd = XferCommand::new(CP).unwrap();
+
+        assert_eq!(
+            cmd.fetch(&url, dest.to_str().unwrap(), false),
+            FetchResult::Ok
+        );
+        assert_eq!(fs::read_to_string(dest.join("foo.db")).unwrap(), "foo");
+        assert!(!dest.join("foo.db.part").exists());
+
+        fs::write(dest.join("foo.db.part"), "stale").unwrap();
+        assert_eq!(
+            cmd.fetch(&url, dest.to_str().unwrap(), true),
+            FetchResult::Ok
+        );
+        assert_eq!(fs::read_to_string(dest.join("foo.db")).unwrap(), "foo");
+
+        let url = format!("file://{}", dir.join("src/missing.db").display());
+        assert_eq!(
+            cmd.fetch(&url, dest.to_str().unwrap(), false),
+            FetchResult::Err
+        );
+        assert!(!dest.join("missing.db").exists());
+    }
+
+    #[test]
+    fn test_fetch_timeout() {
+        let (dir, url) = setup("xfer-timeout");
+        let dest = dir.join("dest");
+        let cmd = XferCommand::new("sh -c 'sleep 5' sh %u")
+            .unwrap()
+            .timeout(Duration::from_millis(100));
+
+        let start = Instant::now();
+        assert_eq!(
+            cmd.fetch(&url, dest.to_str().unwrap(), false),
+            FetchResult::Err
+        );
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_set_xfer_command() {
+        let handle = Alpm::new("/", "tests/db").unwrap();
+        assert_eq!(handle.set_xfer_command("wget"), Err(Error::WrongArgs));
+        handle.set_xfer_command(CP).unwrap();
+    }
+}
@@ -0,0 +1,266 @@
+// Leak and double free checks for the code paths that transfer ownership between Rust and
+// libalpm.
+//
+// Rust allocations are counted by a wrapping global allocator. libalpm allocates with
+// malloc, so on glibc the bytes in use reported by mallinfo are checked too. Both counters
+// are process wide, so every case runs from a single test one after another.
+//
+// Each case is run once to warm up any caches, then repeatedly, and the counters must be
+// back where they started. The number of Rust allocations is counted as well, for checking
+// that memory is moved rather than copied. New code that hands memory to or takes memory from libalpm
+// should add a case here.
+
+use alpm::{
+    Alpm, AlpmListMut, Depend, LogLevel, PrepareResult, SigLevel, Signature, StringList, TransFlag,
+};
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicIsize, AtomicUsize, Ordering};
+
+struct Counting;
+
+static ALLOCATED: AtomicIsize = AtomicIsize::new(0);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size() as isize, Ordering::SeqCst);
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size() as isize, Ordering::SeqCst);
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATED.fetch_add(new_size as isize - layout.size() as isize, Ordering::SeqCst);
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+fn malloc_in_use() -> isize {
+    #[repr(C)]
+    struct MallInfo {
+        arena: i32,
+        ordblks: i32,
+        smblks: i32,
+        hblks: i32,
+        hblkhd: i32,
+        usmblks: i32,
+        fsmblks: i32,
+        uordblks: i32,
+        fordblks: i32,
+        keepcost: i32,
+    }
+
+    extern "C" {
+        fn mallinfo() -> MallInfo;
+    }
+
+    let info = unsafe { mallinfo() };
+    info.uordblks as isize + info.hblkhd as isize
+}
+
+#[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+fn malloc_in_use() -> isize {
+    0
+}
+
+const ITERATIONS: usize = 50;
+
+fn allocations<F: FnOnce()>(f: F) -> usize {
+    let before = ALLOCATIONS.load(Ordering::SeqCst);
+    f();
+    ALLOCATIONS.load(Ordering::SeqCst) - before
+}
+
+fn check<F: FnMut()>(name: &str, mut f: F) {
+    f();
+
+    let rust = ALLOCATED.load(Ordering::SeqCst);
+    let malloc = malloc_in_use();
+
+    for _ in 0..ITERATIONS {
+        f();
+    }
+
+    let rust_leaked = ALLOCATED.load(Ordering::SeqCst) - rust;
+    let malloc_leaked = malloc_in_use() - malloc;
+    assert_eq!(
+        rust_leaked, 0,
+        "{}: leaked {} bytes of Rust memory",
+        name, rust_leaked
+    );
+    assert_eq!(
+        malloc_leaked, 0,
+        "{}: leaked {} bytes of malloc memory",
+        name, malloc_leaked
+    );
+}
+
+#[test]
+fn leaks() {
+    let mut handle = Alpm::new("/", "tests/db").unwrap();
+    handle.register_syncdb("core", SigLevel::NONE).unwrap();
+
+    check("string list", || {
+        let items = ["a", "b", "c"].iter().map(|s| s.to_string());
+        let mut list = AlpmListMut::from_items(&handle, items);
+        list.push_str("d");
+        list.extend(["e", "f"].iter().copied());
+        let _ = list.iter().collect::<Vec<_>>();
+    });
+
+    check("string list into_iter", || {
+        let items = ["a", "b", "c"].iter().map(|s| s.to_string());
+        let list = AlpmListMut::from_items(&handle, items);
+        let _ = list.into_iter().next();
+    });
+
+    check("set_ignorepkgs iterator", || {
+        handle.set_ignorepkgs(["a", "b"].iter().copied()).unwrap();
+        handle
+            .set_ignorepkgs(vec!["c".to_string()].into_iter())
+            .unwrap();
+    });
+
+    check("set_ignorepkgs StringList", || {
+        let owned = vec!["a".to_string(), "b\0c".to_string()];
+        handle.set_ignorepkgs(StringList(owned)).unwrap();
+        handle.set_ignorepkgs(StringList(&["c", "d"])).unwrap();
+    });
+
+    // Strings with room for the nul are moved into the list without allocating, borrowed
+    // ones are copied once each.
+    let names = (0..5000)
+        .map(|i| {
+            let name = format!("pkg{}", i);
+            let mut s = String::with_capacity(name.len() + 1);
+            s.push_str(&name);
+            s
+        })
+        .collect::<Vec<_>>();
+    let copies = allocations(|| handle.set_ignorepkgs(StringList(&names)).unwrap());
+    assert_eq!(copies, names.len());
+    let moves = allocations(|| handle.set_ignorepkgs(StringList(names)).unwrap());
+    assert_eq!(moves, 0);
+    assert_eq!(handle.ignorepkgs().len(), 5000);
+
+    check("string list removal", || {
+        let items = ["a", "b", "c", "d", "e"].iter().map(|s| s.to_string());
+        let mut list = AlpmListMut::from_items(&handle, items);
+        assert_eq!(list.pop().as_deref(), Some("e"));
+        assert_eq!(list.remove_first(|s| s == "b").as_deref(), Some("b"));
+        assert!(list.remove_str("c"));
+        list.clear();
+        assert!(list.is_empty());
+    });
+
+    check("depend list removal", || {
+        let deps = ["foo=1", "bar>2", "baz", "qux<3"]
+            .iter()
+            .map(|d| Depend::new(*d));
+        let mut list = AlpmListMut::from_items(&handle, deps);
+        assert_eq!(list.pop().unwrap().name(), "qux");
+        assert_eq!(
+            list.remove_first(|d| d.name() == "bar").unwrap().name(),
+            "bar"
+        );
+        list.clear();
+        assert!(list.is_empty());
+    });
+
+    check("depends copy", || {
+        let db = handle.syncdbs().first().unwrap();
+        let depends = db.pkg("linux").unwrap().depends();
+        let copy = depends.to_list_mut();
+        assert_eq!(copy.len(), depends.len());
+        assert_eq!(depends.to_vec().len(), depends.len());
+    });
+
+    check("owned depend list copy", || {
+        let db = handle.syncdbs().first().unwrap();
+        let depends = db.pkg("linux").unwrap().depends();
+        let owned = AlpmListMut::from_items(&handle, depends.iter().map(|d| d.to_depend()));
+        let copy = owned.to_list_mut();
+        drop(owned);
+        assert_eq!(copy.to_vec().len(), depends.len());
+    });
+
+    check("set_assume_installed temporaries", || {
+        let deps = [Depend::new("foo=1"), Depend::new("bar>2")];
+        handle.set_assume_installed(deps.iter()).unwrap();
+    });
+
+    check("required_by", || {
+        let pkg = handle.localdb().pkg("ncurses").unwrap();
+        let _ = pkg.required_by().iter().collect::<Vec<_>>();
+        let _ = pkg.optional_for();
+    });
+
+    check("signature", || {
+        let db = handle.syncdbs().first().unwrap();
+        let pkg = db.pkg("linux").unwrap();
+        let sig = pkg.sig().unwrap();
+        let decoded = Signature::decode(pkg.base64_sig().unwrap()).unwrap();
+        assert_eq!(sig.len(), decoded.len());
+    });
+
+    check("log callback", || {
+        handle.set_log_cb(
+            String::from("data"),
+            |_: LogLevel, _: &str, _: &mut String| {},
+        );
+        handle.set_log_cb(vec![0u8; 64], |_: LogLevel, _: &str, _: &mut Vec<u8>| {});
+        let raw = handle.take_raw_log_cb();
+        handle.set_raw_log_cb(raw);
+    });
+
+    check("target file conflicts", || {
+        // A package conflicts with every file of a second copy of itself.
+        let vifm = handle.localdb().pkg("vifm").unwrap();
+        let conflicts = handle
+            .check_target_file_conflicts([vifm, vifm].iter())
+            .unwrap();
+        assert!(!conflicts.is_empty());
+        let copy = conflicts.to_list_mut();
+        drop(conflicts);
+        drop(copy);
+    });
+
+    check("prepare error data", || {
+        handle.trans_init(TransFlag::NO_LOCK).unwrap();
+        let pkg = handle.localdb().pkg("ncurses").unwrap();
+        handle.trans_remove_pkg(pkg).unwrap();
+        match handle.trans_prepare() {
+            Err((PrepareResult::UnsatisfiedDeps(missing), _)) => {
+                assert!(!missing.is_empty());
+                let _ = missing.iter().map(|m| m.to_string()).collect::<Vec<_>>();
+            }
+            _ => panic!("expected unsatisfied deps"),
+        }
+        handle.trans_release().unwrap();
+    });
+
+    check("prepare error data copy", || {
+        handle.trans_init(TransFlag::NO_LOCK).unwrap();
+        let pkg = handle.localdb().pkg("ncurses").unwrap();
+        handle.trans_remove_pkg(pkg).unwrap();
+        match handle.trans_prepare() {
+            Err((PrepareResult::UnsatisfiedDeps(missing), _)) => {
+                let copy = missing.to_list_mut();
+                drop(missing);
+                assert!(!copy.is_empty());
+            }
+            _ => panic!("expected unsatisfied deps"),
+        }
+        handle.trans_release().unwrap();
+    });
+}